@@ -0,0 +1,43 @@
+// Compares a chain of fixed-arity compute cells against the same chain built
+// from slice-based closures, and against cells wide enough that their
+// dependency values no longer fit inline. Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use react::{CellId, Reactor};
+
+const CELLS: usize = 1_000;
+const WRITES: i64 = 1_000;
+
+fn run(name: &str, build: impl Fn(&mut Reactor<i64>, CellId, &[CellId]) -> CellId) {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(0);
+    let others: Vec<CellId> = (1..=3).map(|i| CellId::Input(reactor.create_input(i))).collect();
+    let mut last = CellId::Input(input);
+    for _ in 0..CELLS {
+        last = build(&mut reactor, last, &others);
+    }
+
+    let start = Instant::now();
+    for i in 1..=WRITES {
+        reactor.set_value(input, i);
+        black_box(reactor.value(last));
+    }
+    let per_write = start.elapsed() / WRITES as u32;
+    println!("{name:<12} {per_write:>10.2?} per write");
+}
+
+fn main() {
+    run("compute2", |r, a, o| CellId::Compute(r.create_compute2(a, o[0], |x, y| x + y).unwrap()));
+    run("slice, 2", |r, a, o| {
+        CellId::Compute(r.create_compute(&[a, o[0]], |v| v[0] + v[1]).unwrap())
+    });
+    run("compute3", |r, a, o| {
+        CellId::Compute(r.create_compute3(a, o[0], o[1], |x, y, z| x + y - z).unwrap())
+    });
+    run("slice, 4", |r, a, o| {
+        let f = |v: &[i64]| v[0] + v[1] - v[2] + v[3] - v[3];
+        CellId::Compute(r.create_compute(&[a, o[0], o[1], o[2]], f).unwrap())
+    });
+}
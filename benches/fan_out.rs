@@ -0,0 +1,47 @@
+// A wide fan-out where a write only changes a handful of the cells it
+// reaches. Propagation follows dirty flags and recomputes just the cells with
+// a changed parent; `recompute_all` stands in for recomputing every
+// descendant. Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use react::{CellId, ComputeCellId, Reactor};
+
+const WIDTH: i64 = 2_000;
+const WRITES: i64 = 200;
+
+fn recomputes(reactor: &Reactor<i64>, cells: &[ComputeCellId]) -> u64 {
+    cells.iter().map(|&id| reactor.recompute_count(id).unwrap()).sum()
+}
+
+fn main() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(0);
+    let mut cells: Vec<ComputeCellId> = Vec::new();
+    for i in 1..=WIDTH {
+        // Only changes when the input crosses a multiple of `i`.
+        let bucket = reactor.create_compute(&[CellId::Input(input)], move |v| v[0] / i).unwrap();
+        let below = reactor.create_compute(&[CellId::Compute(bucket)], |v| v[0] * 2).unwrap();
+        cells.extend([bucket, below]);
+    }
+
+    let start = Instant::now();
+    for i in 1..=WRITES {
+        reactor.set_value(input, i);
+        black_box(reactor.value(CellId::Compute(cells[0])));
+    }
+    let dirty_time = start.elapsed() / WRITES as u32;
+    let dirty_runs = recomputes(&reactor, &cells);
+
+    let start = Instant::now();
+    for _ in 1..=WRITES {
+        reactor.recompute_all().unwrap();
+        black_box(reactor.value(CellId::Compute(cells[0])));
+    }
+    let full_time = start.elapsed() / WRITES as u32;
+    let full_runs = recomputes(&reactor, &cells) - dirty_runs;
+
+    println!("dirty flags  {dirty_time:>10.2?} per write, {dirty_runs:>8} closure runs");
+    println!("recompute    {full_time:>10.2?} per write, {full_runs:>8} closure runs");
+}
@@ -0,0 +1,157 @@
+#[cfg(not(feature = "thread-safe"))]
+use alloc::rc::Rc as Shared;
+#[cfg(feature = "thread-safe")]
+use alloc::sync::Arc as Shared;
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, vec::Vec};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    CallbackList, CellId, ComputeCell, ComputeCellId, ConstantCell, ConstantCellId, HashMap,
+    InputCell, InputCellId, Reactor,
+};
+
+#[cfg(not(feature = "thread-safe"))]
+pub type BoxedComputeFn<'a, T> = Box<dyn 'a + Fn(&[T]) -> T>;
+#[cfg(feature = "thread-safe")]
+pub type BoxedComputeFn<'a, T> = Box<dyn 'a + Fn(&[T]) -> T + Send + Sync>;
+
+/// The topology and values of a reactor, without any closures.
+///
+/// With the `serde` feature enabled this can be serialized; compute functions
+/// have to be supplied again through `Reactor::rehydrate` when loading it.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReactorBlueprint<T> {
+    inputs: Vec<(InputCellId, T, T)>,
+    constants: Vec<(ConstantCellId, T)>,
+    compute: Vec<(ComputeCellId, Vec<CellId>, T)>,
+    // Each alias with the input it stands for, ordered by alias.
+    aliases: Vec<(InputCellId, InputCellId)>,
+    // The ids the next new cells get, which can be past the largest id above
+    // when cells were removed.
+    next_input_id: usize,
+    next_compute_id: usize,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RehydrateError {
+    MissingFunction(ComputeCellId),
+    NonexistentDependency { cell: ComputeCellId, dependency: CellId },
+    Cycle(Vec<CellId>),
+    // Recomputing the cell from its dependencies gave a different value than
+    // the one stored in the blueprint.
+    Inconsistent(ComputeCellId),
+}
+
+impl<'a, T: Clone + PartialEq> Reactor<'a, T> {
+    pub fn blueprint(&self) -> ReactorBlueprint<T> {
+        let mut inputs = Vec::new();
+        let mut constants = Vec::new();
+        let mut compute = Vec::new();
+        for id in self.topological_order() {
+            match id {
+                CellId::Input(input) => {
+                    let cell = &self.inputs[&id];
+                    inputs.push((input, cell.val.clone(), cell.initial.clone()));
+                }
+                CellId::Constant(constant) => {
+                    constants.push((constant, self.constants[&id].val.clone()));
+                }
+                CellId::Compute(cell_id) => {
+                    let cell = &self.compute[&id];
+                    compute.push((cell_id, cell.parents.clone(), cell.val.clone()));
+                }
+            }
+        }
+        let mut aliases: Vec<(InputCellId, InputCellId)> =
+            self.aliases.iter().map(|(alias, id)| (*alias, *id)).collect();
+        aliases.sort_by_key(|(alias, _)| alias.0);
+        ReactorBlueprint {
+            inputs,
+            constants,
+            compute,
+            aliases,
+            next_input_id: self.next_input_id,
+            next_compute_id: self.next_compute_id,
+        }
+    }
+
+    // Rebuilds a reactor from `blueprint`, keeping every id it records, and
+    // checks that the supplied functions reproduce the stored values.
+    pub fn rehydrate(
+        blueprint: &ReactorBlueprint<T>,
+        mut funcs: HashMap<ComputeCellId, BoxedComputeFn<'a, T>>,
+    ) -> Result<Self, RehydrateError>
+    where
+        T: 'a,
+    {
+        let mut reactor = Reactor::new();
+        reactor.next_input_id = blueprint.next_input_id;
+        reactor.next_compute_id = blueprint.next_compute_id;
+        for (id, val, initial) in &blueprint.inputs {
+            let mut cell = InputCell::new(val.clone(), Vec::new());
+            cell.initial = initial.clone();
+            reactor.inputs.insert(CellId::Input(*id), Box::new(cell));
+            reactor.next_input_id = reactor.next_input_id.max(id.0 + 1);
+        }
+        for &(alias, id) in &blueprint.aliases {
+            reactor.aliases.insert(alias, id);
+            reactor.next_input_id = reactor.next_input_id.max(alias.0 + 1);
+        }
+        for (id, val) in &blueprint.constants {
+            let cell = ConstantCell { val: val.clone(), children: Vec::new(), label: None };
+            reactor.constants.insert(CellId::Constant(*id), Box::new(cell));
+        }
+        for (id, parents, val) in &blueprint.compute {
+            let func = funcs.remove(id).ok_or(RehydrateError::MissingFunction(*id))?;
+            let cell = ComputeCell {
+                val: val.clone(),
+                children: Vec::new(),
+                parents: parents.clone(),
+                callbacks: CallbackList::new(),
+                func: Shared::new(move |v: &[T]| Ok(func(v))),
+                preview: None,
+                error: None,
+                changed_at: 0,
+                frozen: false,
+                history: None,
+                label: None,
+                fallback: None,
+                recomputes: 0,
+            };
+            reactor.compute.insert(CellId::Compute(*id), Box::new(cell));
+            reactor.next_compute_id = reactor.next_compute_id.max(id.0 + 1);
+        }
+
+        for (id, parents, _) in &blueprint.compute {
+            for &dependency in parents {
+                match reactor.children_of_mut(dependency) {
+                    Some(children) => children.push(CellId::Compute(*id)),
+                    None => {
+                        let cell = *id;
+                        return Err(RehydrateError::NonexistentDependency { cell, dependency });
+                    }
+                }
+            }
+        }
+        for (id, parents, _) in &blueprint.compute {
+            if let Some(path) = reactor.find_cycle(CellId::Compute(*id), parents) {
+                return Err(RehydrateError::Cycle(path));
+            }
+        }
+
+        for id in reactor.topological_order() {
+            if let CellId::Compute(cell_id) = id {
+                let cell = &reactor.compute[&id];
+                let values = reactor.parent_values(cell).unwrap();
+                if (cell.func)(&values).ok().as_ref() != Some(&cell.val) {
+                    return Err(RehydrateError::Inconsistent(cell_id));
+                }
+            }
+        }
+        Ok(reactor)
+    }
+}
@@ -0,0 +1,163 @@
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, string::String, string::ToString, vec, vec::Vec};
+
+use crate::{CellId, CreateCellError, HashMap, MaybeSend, MaybeSync, Reactor};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    DuplicateName(String),
+    MissingReference { cell: String, missing: String },
+    Cycle(Vec<String>),
+    InvalidCell(String, CreateCellError),
+}
+
+#[cfg(not(feature = "thread-safe"))]
+type BuilderFn<'a, T> = Box<dyn 'a + Fn(&[T]) -> T>;
+#[cfg(feature = "thread-safe")]
+type BuilderFn<'a, T> = Box<dyn 'a + Fn(&[T]) -> T + Send + Sync>;
+
+enum Declaration<'a, T> {
+    Input(T),
+    Compute(Vec<String>, BuilderFn<'a, T>),
+}
+
+enum Visit {
+    Enter(usize),
+    Exit(usize),
+}
+
+/// Collects named cell declarations in any order and wires them into a
+/// `Reactor` once the whole graph is known.
+///
+/// ```
+/// use react::{CellId, ReactorBuilder};
+///
+/// let mut builder = ReactorBuilder::new();
+/// builder
+///     .compute("total", &["a", "b"], |v| v[0] + v[1])
+///     .input("a", 1)
+///     .input("b", 2);
+/// let (reactor, ids) = builder.build_with_ids().unwrap();
+/// assert_eq!(reactor.value(ids["total"]), Some(3));
+/// ```
+pub struct ReactorBuilder<'a, T> {
+    declarations: Vec<(String, Declaration<'a, T>)>,
+}
+
+impl<'a, T: Clone + PartialEq + 'a> ReactorBuilder<'a, T> {
+    pub fn new() -> Self {
+        Self { declarations: Vec::new() }
+    }
+
+    pub fn input(&mut self, name: impl Into<String>, initial: T) -> &mut Self {
+        self.declarations.push((name.into(), Declaration::Input(initial)));
+        self
+    }
+
+    pub fn compute<F: Fn(&[T]) -> T + MaybeSend + MaybeSync + 'a>(
+        &mut self,
+        name: impl Into<String>,
+        dependencies: &[&str],
+        compute_func: F,
+    ) -> &mut Self {
+        let dependencies = dependencies.iter().map(|d| d.to_string()).collect();
+        self.declarations.push((
+            name.into(),
+            Declaration::Compute(dependencies, Box::new(compute_func)),
+        ));
+        self
+    }
+
+    pub fn build(self) -> Result<Reactor<'a, T>, BuildError> {
+        self.build_with_ids().map(|(reactor, _)| reactor)
+    }
+
+    // Like `build`, but also returns the id each name was resolved to.
+    pub fn build_with_ids(
+        self,
+    ) -> Result<(Reactor<'a, T>, HashMap<String, CellId>), BuildError> {
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for (i, (name, _)) in self.declarations.iter().enumerate() {
+            if index.insert(name, i).is_some() {
+                return Err(BuildError::DuplicateName(name.clone()));
+            }
+        }
+
+        let mut edges: Vec<Vec<usize>> = Vec::new();
+        for (name, decl) in &self.declarations {
+            let deps = match decl {
+                Declaration::Input(_) => Vec::new(),
+                Declaration::Compute(deps, _) => deps
+                    .iter()
+                    .map(|d| {
+                        index.get(d.as_str()).copied().ok_or_else(|| BuildError::MissingReference {
+                            cell: name.clone(),
+                            missing: d.clone(),
+                        })
+                    })
+                    .collect::<Result<Vec<usize>, BuildError>>()?,
+            };
+            edges.push(deps);
+        }
+
+        let order = self.order(&edges)?;
+        // `index` borrows the names, so it has to go before they are moved out.
+        drop(index);
+        let mut slots: Vec<Option<(String, Declaration<'a, T>)>> =
+            self.declarations.into_iter().map(Some).collect();
+        let mut reactor = Reactor::new();
+        let mut ids: HashMap<String, CellId> = HashMap::new();
+        for i in order {
+            let (name, decl) = slots[i].take().unwrap();
+            let id = match decl {
+                Declaration::Input(initial) => CellId::Input(reactor.create_input(initial)),
+                Declaration::Compute(deps, func) => {
+                    let deps: Vec<CellId> = deps.iter().map(|d| ids[d]).collect();
+                    match reactor.create_compute(&deps, func) {
+                        Ok(id) => CellId::Compute(id),
+                        Err(e) => return Err(BuildError::InvalidCell(name, e)),
+                    }
+                }
+            };
+            ids.insert(name, id);
+        }
+        Ok((reactor, ids))
+    }
+
+    // Orders declarations so each comes after everything it depends on,
+    // reporting the first dependency cycle found.
+    fn order(&self, edges: &[Vec<usize>]) -> Result<Vec<usize>, BuildError> {
+        let mut order: Vec<usize> = Vec::new();
+        let mut done = vec![false; edges.len()];
+        let mut active: Vec<usize> = Vec::new();
+        for start in 0..edges.len() {
+            let mut work = vec![Visit::Enter(start)];
+            while let Some(visit) = work.pop() {
+                match visit {
+                    Visit::Enter(i) => {
+                        if done[i] {
+                            continue;
+                        }
+                        if let Some(pos) = active.iter().position(|&a| a == i) {
+                            let mut cycle: Vec<String> = active[pos..]
+                                .iter()
+                                .map(|&a| self.declarations[a].0.clone())
+                                .collect();
+                            cycle.push(self.declarations[i].0.clone());
+                            return Err(BuildError::Cycle(cycle));
+                        }
+                        active.push(i);
+                        work.push(Visit::Exit(i));
+                        work.extend(edges[i].iter().rev().map(|&d| Visit::Enter(d)));
+                    }
+                    Visit::Exit(i) => {
+                        active.pop();
+                        done[i] = true;
+                        order.push(i);
+                    }
+                }
+            }
+        }
+        Ok(order)
+    }
+}
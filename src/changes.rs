@@ -0,0 +1,117 @@
+use alloc::collections::VecDeque;
+#[cfg(not(feature = "thread-safe"))]
+use alloc::rc::Rc as Shared;
+#[cfg(feature = "thread-safe")]
+use alloc::sync::Arc as Shared;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+
+use crate::sync::Lock;
+use crate::{ComputeCellId, MaybeSend, Reactor};
+
+// Shared between a future or stream and the callback feeding it. Once the
+// receiving side is dropped the callback stops queueing values.
+struct Channel<T> {
+    values: VecDeque<T>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+impl<T> Channel<T> {
+    fn new() -> Shared<Lock<Self>> {
+        Shared::new(Lock::new(Self { values: VecDeque::new(), waker: None, closed: false }))
+    }
+
+    fn push(&mut self, value: T) {
+        if self.closed {
+            return;
+        }
+        self.values.push_back(value);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<T> {
+        match self.values.pop_front() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                self.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Resolves with the value of a compute cell the next time it changes.
+///
+/// The future doesn't borrow the reactor, since it could never resolve while
+/// the writes it waits for were locked out; it is fed by a one-shot callback.
+pub struct ChangeFuture<T> {
+    shared: Shared<Lock<Channel<T>>>,
+}
+
+impl<T> Future for ChangeFuture<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        self.shared.borrow_mut().poll_next(cx)
+    }
+}
+
+impl<T> Drop for ChangeFuture<T> {
+    fn drop(&mut self) {
+        self.shared.borrow_mut().closed = true;
+    }
+}
+
+/// Yields every value a compute cell takes from now on, in order.
+pub struct ChangeStream<T> {
+    shared: Shared<Lock<Channel<T>>>,
+}
+
+impl<T> Stream for ChangeStream<T> {
+    type Item = T;
+
+    // The cell may always change again, so the stream never ends.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.shared.borrow_mut().poll_next(cx).map(Some)
+    }
+}
+
+impl<T> Drop for ChangeStream<T> {
+    fn drop(&mut self) {
+        self.shared.borrow_mut().closed = true;
+    }
+}
+
+impl<'a, T: Clone + PartialEq + MaybeSend + 'a> Reactor<'a, T> {
+    pub fn changed(&mut self, id: ComputeCellId) -> Option<ChangeFuture<T>> {
+        let shared = Channel::new();
+        let (sender, alive) = (Shared::clone(&shared), Shared::clone(&shared));
+        self.add_callback_while(
+            id,
+            true,
+            move || !alive.borrow_mut().closed,
+            move |value| sender.borrow_mut().push(value),
+        )?;
+        Some(ChangeFuture { shared })
+    }
+
+    // Once the stream is dropped, the callback behind it is dropped as well
+    // the next time the cell changes or gets another callback.
+    pub fn changes(&mut self, id: ComputeCellId) -> Option<ChangeStream<T>> {
+        let shared = Channel::new();
+        let (sender, alive) = (Shared::clone(&shared), Shared::clone(&shared));
+        self.add_callback_while(
+            id,
+            false,
+            move || !alive.borrow_mut().closed,
+            move |value| sender.borrow_mut().push(value),
+        )?;
+        Some(ChangeStream { shared })
+    }
+}
@@ -0,0 +1,50 @@
+use core::fmt;
+
+use crate::{CellId, ComputeCellId, CreateCellError, Reactor};
+
+/// Addition that reports overflow instead of panicking or wrapping.
+/// Implemented for the primitive integer types.
+pub trait CheckedAdd: Sized {
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+}
+
+macro_rules! impl_checked_add {
+    ($($t:ty),*) => {
+        $(impl CheckedAdd for $t {
+            fn checked_add(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *other)
+            }
+        })*
+    };
+}
+
+impl_checked_add!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// The error a `create_checked_sum` cell fails with once its sum no longer
+/// fits in `T`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SumOverflow;
+
+impl fmt::Display for SumOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the sum overflowed")
+    }
+}
+
+impl core::error::Error for SumOverflow {}
+
+impl<'a, T: Clone + PartialEq + CheckedAdd + 'a> Reactor<'a, T> {
+    // A fallible cell: while the sum overflows, `value` is `None` and
+    // `error::<SumOverflow>` says why. The initial sum has to fit.
+    pub fn create_checked_sum(
+        &mut self,
+        dependencies: &[CellId],
+    ) -> Result<ComputeCellId, CreateCellError> {
+        self.create_try_compute(dependencies, |v: &[T]| {
+            let (first, rest) = v.split_first().unwrap();
+            rest.iter()
+                .try_fold(first.clone(), |sum, x| sum.checked_add(x))
+                .ok_or(SumOverflow)
+        })
+    }
+}
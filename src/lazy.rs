@@ -0,0 +1,116 @@
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    CallbackId, CellId, ComputeCellId, CreateCellError, HashSet, InputCellId, MaybeSend,
+    MaybeSync, Reactor, RemoveCallbackError,
+};
+
+/// A reactor that defers recomputation until a value is read.
+///
+/// `set_value` only marks the cells downstream of the input as dirty, and
+/// `value` brings the requested cell (and whichever of its ancestors are dirty)
+/// up to date before returning it. This saves work when only a few of many
+/// compute cells are ever read, at the cost of making callbacks pull-driven:
+/// a callback fires when a read finds that its cell changed since the last
+/// time it was computed, not when the input was written. Cells that are never
+/// read never fire.
+pub struct LazyReactor<'a, T> {
+    reactor: Reactor<'a, T>,
+    dirty: HashSet<CellId>,
+}
+
+impl<'a, T: Clone + PartialEq> LazyReactor<'a, T> {
+    pub fn new() -> Self {
+        Self { reactor: Reactor::new(), dirty: HashSet::new() }
+    }
+
+    pub fn create_input(&mut self, initial: T) -> InputCellId {
+        self.reactor.create_input(initial)
+    }
+
+    // New cells are computed from up-to-date values, so their dirty
+    // dependencies are refreshed first.
+    pub fn create_compute<F: Fn(&[T]) -> T + MaybeSend + MaybeSync + 'a>(
+        &mut self,
+        dependencies: &[CellId],
+        compute_func: F,
+    ) -> Result<ComputeCellId, CreateCellError> {
+        for &d in dependencies {
+            self.refresh(d);
+        }
+        self.reactor.create_compute(dependencies, compute_func)
+    }
+
+    pub fn value(&mut self, id: CellId) -> Option<T> {
+        self.refresh(id);
+        self.reactor.value(id)
+    }
+
+    pub fn set_value(&mut self, id: InputCellId, new_value: T) -> bool {
+        let id = CellId::Input(id);
+        let Some(new_value) = self.reactor.inputs.get(&id).and_then(|c| c.validate(new_value))
+        else {
+            return false;
+        };
+
+        self.reactor.write_input(id, new_value);
+        let mut downstream: Vec<CellId> = Vec::new();
+        self.reactor.get_topo_order(id, &mut downstream, &mut HashSet::new());
+        self.dirty
+            .extend(downstream.into_iter().filter(|c| matches!(c, CellId::Compute(_))));
+        true
+    }
+
+    pub fn add_callback<F: 'a + MaybeSend + FnMut(T)>(
+        &mut self,
+        id: ComputeCellId,
+        callback: F,
+    ) -> Option<CallbackId> {
+        self.reactor.add_callback(id, callback)
+    }
+
+    pub fn remove_callback(
+        &mut self,
+        cell: ComputeCellId,
+        callback: CallbackId,
+    ) -> Result<(), RemoveCallbackError> {
+        self.reactor.remove_callback(cell, callback)
+    }
+
+    pub fn is_dirty(&self, id: ComputeCellId) -> bool {
+        self.dirty.contains(&CellId::Compute(id))
+    }
+
+    // Recomputes the dirty ancestors of `id` (and `id` itself) parents-first,
+    // then fires callbacks for those that changed since they were last computed.
+    fn refresh(&mut self, id: CellId) {
+        if !self.dirty.contains(&id) {
+            return;
+        }
+
+        let mut order: Vec<CellId> = Vec::new();
+        let mut seen: HashSet<CellId> = HashSet::new();
+        let mut work: Vec<(CellId, bool)> = vec![(id, false)];
+        while let Some((cell, expanded)) = work.pop() {
+            if expanded {
+                order.push(cell);
+                continue;
+            }
+            if !self.dirty.contains(&cell) || !seen.insert(cell) {
+                continue;
+            }
+            work.push((cell, true));
+            for &parent in self.reactor.parents_of(cell).iter().rev() {
+                work.push((parent, false));
+            }
+        }
+
+        let before = self.reactor.compute_values(&order);
+        self.reactor.update_compute_cell_value(&order, &order);
+        self.reactor.fire_callbacks(&before);
+        for cell in order {
+            self.dirty.remove(&cell);
+        }
+    }
+}
@@ -1,18 +1,98 @@
-use std::collections::{HashMap, HashSet};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::collections::{BinaryHeap, VecDeque};
+#[cfg(not(feature = "thread-safe"))]
+use alloc::rc::Rc as Shared;
+#[cfg(feature = "thread-safe")]
+use alloc::sync::Arc as Shared;
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, format, string::String, string::ToString, vec, vec::Vec};
+use core::any::Any;
+use core::cmp::Reverse;
+use core::fmt::{self, Debug, Display};
+use core::hash::Hash;
+use core::iter::{Product, Sum};
+use core::mem;
+#[cfg(feature = "timing")]
+use core::time::Duration;
+
+use hashbrown::{hash_map::Entry, HashSet};
+#[cfg(feature = "std")]
+use std::panic::{self, AssertUnwindSafe, UnwindSafe};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The map type taken and returned by the reactor, say by `all_values` and
+/// `compact`. It's `hashbrown`'s with or without `std`, so code written
+/// against one configuration builds in the other.
+pub use hashbrown::HashMap;
+
+mod blueprint;
+mod builder;
+mod checked;
+#[cfg(feature = "async")]
+mod changes;
+mod lazy;
+mod sync;
+mod typed;
+
+pub use blueprint::{BoxedComputeFn, ReactorBlueprint, RehydrateError};
+pub use builder::{BuildError, ReactorBuilder};
+pub use checked::{CheckedAdd, SumOverflow};
+#[cfg(feature = "async")]
+pub use changes::{ChangeFuture, ChangeStream};
+pub use lazy::LazyReactor;
+pub use sync::{MaybeSend, MaybeSync};
+pub use typed::TypedComputeCellId;
+
+use sync::Lock;
+use typed::TypedCell;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ComputeCellId(usize);
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct CallbackId(usize);
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct InputCellId(usize);
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ConstantCellId(usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CellId {
     Input(InputCellId),
     Compute(ComputeCellId),
+    Constant(ConstantCellId),
+}
+
+// The raw index behind an id, for callers keeping their own parallel
+// arrays. Ids can't be built from an index, so only a reactor hands them out.
+macro_rules! impl_index {
+    ($($id:ident),*) => {
+        $(impl $id {
+            pub fn index(&self) -> usize {
+                self.0
+            }
+        })*
+    };
+}
+
+impl_index!(ComputeCellId, CallbackId, InputCellId, ConstantCellId);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CellKind {
+    Input,
+    Compute,
+    Constant,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -22,14 +102,418 @@ pub enum RemoveCallbackError {
 }
 
 #[derive(Debug, PartialEq, Eq)]
-struct InputCell<T> {
+pub enum AddCallbackError {
+    NonexistentCell,
+    LimitReached,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RemoveCellError {
+    NonexistentCell,
+    HasDependents,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RestoreError {
+    ShapeMismatch,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GraphError {
+    DanglingReference { from: CellId, to: CellId },
+    MissingChildLink { parent: CellId, child: CellId },
+    MissingParentLink { parent: CellId, child: CellId },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PropagationError {
+    NonexistentCell,
+    StepLimitExceeded,
+    Reentrant,
+    Rejected,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CreateCellError {
+    NonexistentDependency(CellId),
+    Cycle(Vec<CellId>),
+    ComputeFailed,
+    NoDependencies,
+    // The compute cell being rewired doesn't exist.
+    NonexistentCell,
+    // A cell can depend on another only once, so each dependency has a
+    // single back-edge and there is no doubt which slot a value fills.
+    DuplicateDependency(CellId),
+    // `create_switching_compute` was given no closures to switch between.
+    NoFunctions,
+    // Rewiring the cell was undone because it couldn't be recomputed.
+    Propagation(PropagationError),
+}
+
+impl Display for CreateCellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NonexistentDependency(id) => {
+                write!(f, "dependency {} does not exist", node_name(*id))
+            }
+            Self::Cycle(path) => {
+                let path: Vec<String> = path.iter().map(|&id| node_name(id)).collect();
+                write!(f, "dependency cycle: {}", path.join(" -> "))
+            }
+            Self::ComputeFailed => write!(f, "the cell could not be computed"),
+            Self::NoDependencies => write!(f, "a compute cell needs at least one dependency"),
+            Self::NonexistentCell => write!(f, "the compute cell does not exist"),
+            Self::DuplicateDependency(id) => {
+                write!(f, "dependency {} is listed more than once", node_name(*id))
+            }
+            Self::NoFunctions => write!(f, "a switching cell needs at least one function"),
+            Self::Propagation(PropagationError::StepLimitExceeded) => {
+                write!(f, "recomputing the cell would exceed the step limit")
+            }
+            Self::Propagation(_) => write!(f, "the cell could not be recomputed"),
+        }
+    }
+}
+
+impl core::error::Error for CreateCellError {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReactorSnapshot<T> {
+    values: HashMap<CellId, T>,
+    // Compute cells that were in an error state, whose value in `values` is
+    // the last one they computed successfully.
+    errored: HashSet<CellId>,
+}
+
+/// Everything `cell_info` knows about a cell at the time it was called.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellInfo<T> {
+    value: Option<T>,
+    parents: Vec<CellId>,
+    children: Vec<CellId>,
+    callback_count: usize,
+}
+
+impl<T> CellInfo<T> {
+    // `None` while a compute cell is in an error state.
+    pub fn value(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+
+    pub fn parents(&self) -> &[CellId] {
+        &self.parents
+    }
+
+    pub fn children(&self) -> &[CellId] {
+        &self.children
+    }
+
+    pub fn callback_count(&self) -> usize {
+        self.callback_count
+    }
+}
+
+/// A stateful alternative to a callback closure, registered with
+/// `Reactor::add_observer`.
+pub trait CellObserver<T>: MaybeSend {
+    fn on_change(&mut self, id: ComputeCellId, value: T);
+}
+
+/// An input write recorded by the change log, with every compute cell whose
+/// settled value changed because of it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChangeLogEntry<T> {
+    generation: u64,
+    input: InputCellId,
+    old: T,
+    new: T,
+    affected: Vec<(ComputeCellId, T)>,
+}
+
+impl<T> ChangeLogEntry<T> {
+    // The generation the write settled in.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn input(&self) -> InputCellId {
+        self.input
+    }
+
+    pub fn old_value(&self) -> &T {
+        &self.old
+    }
+
+    pub fn new_value(&self) -> &T {
+        &self.new
+    }
+
+    // In topological order. Inputs written in the same batch or transaction
+    // settle together and share this list.
+    pub fn affected(&self) -> &[(ComputeCellId, T)] {
+        &self.affected
+    }
+}
+
+/// What the last propagation did. `elapsed` is only measured with the
+/// `timing` feature, which needs `std::time::Instant`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropagationMetrics {
+    visited: usize,
+    changed: usize,
+    callbacks_fired: usize,
+    #[cfg(feature = "timing")]
+    elapsed: Duration,
+}
+
+impl PropagationMetrics {
+    // Compute cells walked, whether or not they had to be recomputed.
+    pub fn visited(&self) -> usize {
+        self.visited
+    }
+
+    pub fn changed(&self) -> usize {
+        self.changed
+    }
+
+    // Includes calls to `subscribe_all` observers.
+    pub fn callbacks_fired(&self) -> usize {
+        self.callbacks_fired
+    }
+
+    #[cfg(feature = "timing")]
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+// Turns a value written to an input into the one to store, or `None` to
+// refuse the write.
+#[cfg(not(feature = "thread-safe"))]
+type InputValidator<'a, T> = Shared<dyn 'a + Fn(T) -> Option<T>>;
+#[cfg(feature = "thread-safe")]
+type InputValidator<'a, T> = Shared<dyn 'a + Fn(T) -> Option<T> + Send + Sync>;
+
+struct InputCell<'a, T> {
     val: T,
+    initial: T,
     children: Vec<CellId>,
+    callbacks: CallbackList<'a, T>,
+    label: Option<String>,
+    validator: Option<InputValidator<'a, T>>,
 }
 
-impl<T: Copy> InputCell<T> {
+impl<'a, T: Clone> InputCell<'a, T> {
     fn new(val: T, children: Vec<CellId>) -> Self {
-        Self { val, children }
+        Self {
+            initial: val.clone(),
+            val,
+            children,
+            callbacks: CallbackList::new(),
+            label: None,
+            validator: None,
+        }
+    }
+
+    fn validate(&self, val: T) -> Option<T> {
+        match &self.validator {
+            Some(validator) => validator(val),
+            None => Some(val),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ConstantCell<T> {
+    val: T,
+    children: Vec<CellId>,
+    label: Option<String>,
+}
+
+// The error a fallible compute cell produced. It is type-erased so a reactor
+// can mix cells with different error types, and shared so the same error can
+// be reported by every cell it cascades into.
+#[cfg(not(feature = "thread-safe"))]
+type CellError = Shared<dyn Any>;
+#[cfg(feature = "thread-safe")]
+type CellError = Shared<dyn Any + Send + Sync>;
+
+// Reference counted so `fork` can share the closure with the new reactor.
+#[cfg(not(feature = "thread-safe"))]
+type ComputeFn<'a, T> = Shared<dyn 'a + Fn(&[T]) -> Result<T, CellError>>;
+#[cfg(feature = "thread-safe")]
+type ComputeFn<'a, T> = Shared<dyn 'a + Fn(&[T]) -> Result<T, CellError> + Send + Sync>;
+
+// A compute closure the caller keeps a handle to, so the same instance can
+// back cells in several reactors.
+#[cfg(not(feature = "thread-safe"))]
+pub type SharedComputeFn<'a, T> = Shared<dyn 'a + Fn(&[T]) -> T>;
+#[cfg(feature = "thread-safe")]
+pub type SharedComputeFn<'a, T> = Shared<dyn 'a + Fn(&[T]) -> T + Send + Sync>;
+
+// Callbacks are shared with forks as well, so they live behind a lock.
+#[cfg(not(feature = "thread-safe"))]
+type ValueFn<'a, T> = Shared<Lock<dyn 'a + FnMut(T)>>;
+#[cfg(feature = "thread-safe")]
+type ValueFn<'a, T> = Shared<Lock<dyn 'a + FnMut(T) + Send>>;
+
+#[cfg(not(feature = "thread-safe"))]
+type WithPrevFn<'a, T> = Shared<Lock<dyn 'a + FnMut(T, T)>>;
+#[cfg(feature = "thread-safe")]
+type WithPrevFn<'a, T> = Shared<Lock<dyn 'a + FnMut(T, T) + Send>>;
+
+#[cfg(not(feature = "thread-safe"))]
+type WithSourceFn<'a, T> = Shared<Lock<dyn 'a + FnMut(T, InputCellId)>>;
+#[cfg(feature = "thread-safe")]
+type WithSourceFn<'a, T> = Shared<Lock<dyn 'a + FnMut(T, InputCellId) + Send>>;
+
+// Observers are told which cell changed when they run rather than when they
+// were added, since `compact` may have renumbered it in between.
+#[cfg(not(feature = "thread-safe"))]
+type WithCellFn<'a, T> = Shared<Lock<dyn 'a + FnMut(ComputeCellId, T)>>;
+#[cfg(feature = "thread-safe")]
+type WithCellFn<'a, T> = Shared<Lock<dyn 'a + FnMut(ComputeCellId, T) + Send>>;
+
+#[derive(Clone)]
+enum CallbackFn<'a, T> {
+    Value(ValueFn<'a, T>),
+    WithPrev(WithPrevFn<'a, T>),
+    WithSource(WithSourceFn<'a, T>),
+    WithCell(WithCellFn<'a, T>),
+}
+
+// Given the old and new value, decides whether a callback should run.
+#[cfg(not(feature = "thread-safe"))]
+type CallbackFilter<'a, T> = Shared<dyn 'a + Fn(&T, &T) -> bool>;
+#[cfg(feature = "thread-safe")]
+type CallbackFilter<'a, T> = Shared<dyn 'a + Fn(&T, &T) -> bool + Send + Sync>;
+
+// Whether whatever a callback feeds is still around to take values.
+#[cfg(not(feature = "thread-safe"))]
+type AliveFn<'a> = Shared<dyn 'a + Fn() -> bool>;
+#[cfg(feature = "thread-safe")]
+type AliveFn<'a> = Shared<dyn 'a + Fn() -> bool + Send + Sync>;
+
+#[derive(Clone)]
+struct Callback<'a, T> {
+    func: CallbackFn<'a, T>,
+    once: bool,
+    priority: i32,
+    filter: Option<CallbackFilter<'a, T>>,
+    // Once this returns `false` the callback is dropped without running.
+    alive: Option<AliveFn<'a>>,
+}
+
+// Callbacks in insertion order. Removal leaves a tombstone so survivors keep
+// their relative order; the slots are compacted once tombstones dominate.
+#[derive(Clone)]
+struct CallbackList<'a, T> {
+    slots: Vec<Option<(CallbackId, Callback<'a, T>)>>,
+    index: HashMap<CallbackId, usize>,
+}
+
+impl<'a, T> CallbackList<'a, T> {
+    fn new() -> Self {
+        Self { slots: Vec::new(), index: HashMap::new() }
+    }
+
+    fn insert(&mut self, id: CallbackId, callback: Callback<'a, T>) {
+        self.index.insert(id, self.slots.len());
+        self.slots.push(Some((id, callback)));
+    }
+
+    fn remove(&mut self, id: &CallbackId) -> Option<Callback<'a, T>> {
+        let slot = self.index.remove(id)?;
+        let (_, callback) = self.slots[slot].take().unwrap();
+        if self.index.len() < self.slots.len() / 2 {
+            self.compact();
+        }
+        Some(callback)
+    }
+
+    fn retain<F: FnMut(&Callback<'a, T>) -> bool>(&mut self, mut keep: F) {
+        for slot in self.slots.iter_mut() {
+            if slot.as_ref().is_some_and(|(_, cb)| !keep(cb)) {
+                let (id, _) = slot.take().unwrap();
+                self.index.remove(&id);
+            }
+        }
+        if self.index.len() < self.slots.len() / 2 {
+            self.compact();
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    // One-shot callbacks would fire once per reactor, and those feeding a
+    // stream belong to the reactor the stream came from, so neither carries
+    // over to a fork.
+    fn fork(&self) -> Self
+    where
+        T: Clone,
+    {
+        let mut list = self.clone();
+        list.retain(|cb| !cb.once && cb.alive.is_none());
+        list
+    }
+
+    fn prune(&mut self) {
+        self.retain(|cb| cb.alive.as_ref().is_none_or(|alive| alive()));
+    }
+
+    fn ids(&self) -> Vec<CallbackId> {
+        self.slots.iter().flatten().map(|(id, _)| *id).collect()
+    }
+
+    fn values_mut(&mut self) -> impl Iterator<Item = &mut Callback<'a, T>> {
+        self.slots.iter_mut().flatten().map(|(_, cb)| cb)
+    }
+
+    // Higher priorities first; the sort is stable so ties keep insertion
+    // order. One-shot callbacks are dropped once they have run, and callbacks
+    // wanting the source input are skipped when there is none. Returns how
+    // many callbacks ran.
+    fn fire(
+        &mut self,
+        cell: CellId,
+        old_val: &T,
+        new_val: &T,
+        source: Option<InputCellId>,
+    ) -> usize
+    where
+        T: Clone,
+    {
+        self.prune();
+        let mut callbacks: Vec<&mut Callback<'a, T>> = self
+            .values_mut()
+            .filter(|cb| source.is_some() || !matches!(cb.func, CallbackFn::WithSource(_)))
+            .filter(|cb| cb.filter.as_ref().is_none_or(|keep| keep(old_val, new_val)))
+            .collect();
+        callbacks.sort_by_key(|cb| Reverse(cb.priority));
+        let fired = callbacks.len();
+        for cb in callbacks {
+            match &mut cb.func {
+                CallbackFn::Value(f) => (f.borrow_mut())(new_val.clone()),
+                CallbackFn::WithPrev(f) => (f.borrow_mut())(old_val.clone(), new_val.clone()),
+                CallbackFn::WithSource(f) => (f.borrow_mut())(new_val.clone(), source.unwrap()),
+                CallbackFn::WithCell(f) => {
+                    if let CellId::Compute(id) = cell {
+                        (f.borrow_mut())(id, new_val.clone());
+                    }
+                }
+            }
+        }
+        self.retain(|cb| !cb.once);
+        fired
+    }
+
+    fn compact(&mut self) {
+        self.slots.retain(Option::is_some);
+        for (i, (id, _)) in self.slots.iter().flatten().enumerate() {
+            self.index.insert(*id, i);
+        }
     }
 }
 
@@ -37,154 +521,1853 @@ struct ComputeCell<'a, T> {
     val: T,
     children: Vec<CellId>,
     parents: Vec<CellId>,
-    callbacks: HashMap<CallbackId, Box<dyn 'a + FnMut(T)>>,
-    func: Box<dyn 'a + Fn(&[T]) -> T>,
-    cb_id: usize
+    callbacks: CallbackList<'a, T>,
+    func: ComputeFn<'a, T>,
+    // Run by `would_change` instead of `func`, for closures whose own state
+    // a preview mustn't disturb.
+    preview: Option<ComputeFn<'a, T>>,
+    // Set while the cell, or one of its ancestors, failed to compute. `val`
+    // then holds the last good value but is not reported by `value`.
+    error: Option<CellError>,
+    // The generation in which `val` last changed.
+    changed_at: u64,
+    // A frozen cell keeps its value until it is unfrozen.
+    frozen: bool,
+    history: Option<History<T>>,
+    label: Option<String>,
+    // Stands in for the value of any parent that is in an error state.
+    fallback: Option<T>,
+    // How many times `func` has run during propagation.
+    recomputes: u64,
+}
+
+// The most recent settled values of a cell, oldest first. Kept contiguous so
+// it can be handed out as a slice.
+#[derive(Clone)]
+struct History<T> {
+    capacity: usize,
+    values: VecDeque<T>,
+}
+
+impl<T> History<T> {
+    fn push(&mut self, val: T) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back(val);
+        self.values.make_contiguous();
+    }
+}
+
+// The dependency values handed to a compute closure. Most cells have only a
+// few dependencies, and keeping those inline saves an allocation for every
+// cell visited during propagation.
+enum ParentValues<T> {
+    One([T; 1]),
+    Two([T; 2]),
+    Three([T; 3]),
+    Many(Vec<T>),
+}
+
+impl<T> core::ops::Deref for ParentValues<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            ParentValues::One(values) => values,
+            ParentValues::Two(values) => values,
+            ParentValues::Three(values) => values,
+            ParentValues::Many(values) => values,
+        }
+    }
+}
+
+// Dirty compute cells, handed out in the order of the propagation queue.
+struct DirtyQueue<'q> {
+    queue: &'q [CellId],
+    position: HashMap<CellId, usize>,
+    heap: BinaryHeap<Reverse<usize>>,
+    pending: HashSet<CellId>,
+}
+
+impl<'q> DirtyQueue<'q> {
+    fn new(queue: &'q [CellId]) -> Self {
+        let position = queue.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        Self { queue, position, heap: BinaryHeap::new(), pending: HashSet::new() }
+    }
+
+    // Cells that aren't part of the queue are ignored.
+    fn mark(&mut self, id: CellId) {
+        if let Some(&pos) = self.position.get(&id) {
+            if self.pending.insert(id) {
+                self.heap.push(Reverse(pos));
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<CellId> {
+        let Reverse(pos) = self.heap.pop()?;
+        let id = self.queue[pos];
+        self.pending.remove(&id);
+        Some(id)
+    }
+
+    fn is_pending(&self, id: &CellId) -> bool {
+        self.pending.contains(id)
+    }
+}
+
+enum Visit {
+    Enter(CellId),
+    Exit(CellId),
+}
+
+// Behind a lock like callbacks, which keeps the reactor `Sync` without asking
+// the same of observers.
+#[cfg(not(feature = "thread-safe"))]
+type ObserverFn<'a, T> = Box<Lock<dyn 'a + FnMut(CellId, T)>>;
+#[cfg(feature = "thread-safe")]
+type ObserverFn<'a, T> = Box<Lock<dyn 'a + FnMut(CellId, T) + Send>>;
+
+#[derive(Clone)]
+struct Transaction<T> {
+    original: HashMap<CellId, T>,
+    dirty: Vec<CellId>,
 }
 
 pub struct Reactor<'a, T> {
-    inputs: HashMap<CellId, Box<InputCell<T>>>,
+    inputs: HashMap<CellId, Box<InputCell<'a, T>>>,
     compute: HashMap<CellId, Box<ComputeCell<'a, T>>>,
+    constants: HashMap<CellId, Box<ConstantCell<T>>>,
+    observers: Vec<(CallbackId, ObserverFn<'a, T>)>,
+    // Shared by every cell and by `subscribe_all`, so a `CallbackId` names
+    // exactly one callback in the whole reactor.
+    next_callback_id: usize,
+    // Ids are never handed out twice, even after `remove_cell`, so a stale id
+    // can't end up naming a newer cell.
+    next_input_id: usize,
+    next_compute_id: usize,
+    transaction: Option<Transaction<T>>,
+    // Bumped each time a propagation settles with at least one changed cell.
+    // `written_inputs` holds the previous value of every input whose write
+    // has not been propagated yet.
+    generation: u64,
+    written_inputs: Vec<(CellId, T)>,
+    max_propagation_steps: Option<usize>,
+    callback_limit: usize,
+    // While `queue_notifications` is set, changes are recorded here instead
+    // of being passed to callbacks and observers.
+    queue_notifications: bool,
+    notifications: Vec<(CellId, T)>,
+    metrics: Option<PropagationMetrics>,
+    propagating: bool,
+    change_log: Option<Vec<ChangeLogEntry<T>>>,
+    // While a frame is open, the latest value written to each input, in the
+    // order the inputs were first written.
+    frame: Option<Vec<(CellId, T)>>,
+    typed: HashMap<usize, TypedCell<'a, T>>,
+    next_typed_id: usize,
+    // How often each cell's closure ran during the latest propagation.
+    #[cfg(debug_assertions)]
+    recomputed: HashMap<CellId, usize>,
+    // Each alias created by `alias_input`, with the input it stands for.
+    aliases: HashMap<InputCellId, InputCellId>,
 }
 
-impl<'a, T: Copy + PartialEq> Reactor<'a, T> {
+impl<'a, T: Clone + PartialEq> Reactor<'a, T> {
     pub fn new() -> Self {
-        Self { inputs: HashMap::new(), compute: HashMap::new() }
+        Self {
+            inputs: HashMap::new(),
+            compute: HashMap::new(),
+            constants: HashMap::new(),
+            observers: Vec::new(),
+            next_callback_id: 1,
+            next_input_id: 0,
+            next_compute_id: 0,
+            transaction: None,
+            generation: 0,
+            written_inputs: Vec::new(),
+            max_propagation_steps: None,
+            callback_limit: usize::MAX,
+            queue_notifications: false,
+            notifications: Vec::new(),
+            metrics: None,
+            propagating: false,
+            change_log: None,
+            frame: None,
+            typed: HashMap::new(),
+            next_typed_id: 0,
+            #[cfg(debug_assertions)]
+            recomputed: HashMap::new(),
+            aliases: HashMap::new(),
+        }
     }
     
     pub fn create_input(&mut self, _initial: T) -> InputCellId {
-        let id = InputCellId(self.inputs.len());
+        let id = InputCellId(self.next_input_id);
+        self.next_input_id += 1;
         self.inputs.insert(CellId::Input(id), Box::new(InputCell::new(_initial,vec![])));
         id
     }
+
+    // A second id for an existing input. Both ids read and write the same
+    // cell, share its callbacks and validator, and can be used as
+    // dependencies interchangeably; a write through either propagates once,
+    // and callbacks told where a change came from see the id it went through.
+    // Aliases of aliases stand for the original input. Every id keeps the
+    // cell alive: inputs are never removed, and if that ever changes a cell
+    // must only go once none of its aliases are left. Listings such as
+    // `iter_cells` show the cell once, under its original id.
+    pub fn alias_input(&mut self, existing: InputCellId) -> Option<InputCellId> {
+        let backing = *self.aliases.get(&existing).unwrap_or(&existing);
+        if !self.inputs.contains_key(&CellId::Input(backing)) {
+            return None;
+        }
+        let id = InputCellId(self.next_input_id);
+        self.next_input_id += 1;
+        self.aliases.insert(id, backing);
+        Some(id)
+    }
+
+    pub fn create_inputs(&mut self, count: usize, initial: T) -> Vec<InputCellId> {
+        (0..count).map(|_| self.create_input(initial.clone())).collect()
+    }
+
+    pub fn create_inputs_from(&mut self, values: &[T]) -> Vec<InputCellId> {
+        values.iter().map(|val| self.create_input(val.clone())).collect()
+    }
+
+    // Constants can be used as dependencies but have no `InputCellId`, so
+    // there is nothing to pass to `set_value`.
+    pub fn create_constant(&mut self, val: T) -> CellId {
+        let id = CellId::Constant(ConstantCellId(self.constants.len()));
+        let cell = ConstantCell { val, children: Vec::new(), label: None };
+        self.constants.insert(id, Box::new(cell));
+        id
+    }
     
-    pub fn create_compute<F: Fn(&[T]) -> T + 'a>(
+    pub fn create_compute<F: Fn(&[T]) -> T + MaybeSend + MaybeSync + 'a>(
         &mut self,
         _dependencies: &[CellId],
         _compute_func: F,
-    ) -> Result<ComputeCellId, CellId> {
+    ) -> Result<ComputeCellId, CreateCellError> {
+        let (id, values) = self.prepare_compute(_dependencies, None)?;
+        let val = _compute_func(&values);
+        let func = move |v: &[T]| Ok(_compute_func(v));
+        self.insert_compute(id, _dependencies, val, Shared::new(func));
+        Ok(id)
+    }
+
+    // Like `create_compute`, but the closure is one the caller already holds
+    // a `SharedComputeFn` to and is only shared, never copied.
+    pub fn create_compute_shared(
+        &mut self,
+        dependencies: &[CellId],
+        compute_func: SharedComputeFn<'a, T>,
+    ) -> Result<ComputeCellId, CreateCellError>
+    where
+        T: 'a,
+    {
+        self.create_compute(dependencies, move |v: &[T]| compute_func(v))
+    }
+
+    // Instead of failing along with an errored dependency, the cell passes
+    // `fallback` to its closure in that dependency's place.
+    pub fn create_compute_with_fallback<F: Fn(&[T]) -> T + MaybeSend + MaybeSync + 'a>(
+        &mut self,
+        dependencies: &[CellId],
+        fallback: T,
+        compute_func: F,
+    ) -> Result<ComputeCellId, CreateCellError> {
+        let (id, values) = self.prepare_compute(dependencies, Some(&fallback))?;
+        let val = compute_func(&values);
+        let func = move |v: &[T]| Ok(compute_func(v));
+        self.insert_compute(id, dependencies, val, Shared::new(func));
+        self.compute.get_mut(&CellId::Compute(id)).unwrap().fallback = Some(fallback);
+        Ok(id)
+    }
+
+    // A cell whose closure can fail. While failed, `value` returns `None`,
+    // `error` returns the error and every downstream cell fails with it too.
+    // The initial evaluation must succeed since there is no value to fall
+    // back on.
+    pub fn create_try_compute<E, F>(
+        &mut self,
+        dependencies: &[CellId],
+        compute_func: F,
+    ) -> Result<ComputeCellId, CreateCellError>
+    where
+        E: 'static + MaybeSend + MaybeSync,
+        F: Fn(&[T]) -> Result<T, E> + MaybeSend + MaybeSync + 'a,
+    {
+        let (id, values) = self.prepare_compute(dependencies, None)?;
+        let val = compute_func(&values).map_err(|_| CreateCellError::ComputeFailed)?;
+        let func = move |v: &[T]| compute_func(v).map_err(|e| Shared::new(e) as CellError);
+        self.insert_compute(id, dependencies, val, Shared::new(func));
+        Ok(id)
+    }
+
+    pub fn error<E: 'static>(&self, id: ComputeCellId) -> Option<&E> {
+        self.compute
+            .get(&CellId::Compute(id))?
+            .error
+            .as_ref()?
+            .downcast_ref::<E>()
+    }
+
+    // Like `create_compute`, but a panic in the initial evaluation is caught
+    // and reported instead of unwinding through the reactor. Nothing is wired
+    // up until the closure has returned a value. Catching panics needs `std`.
+    #[cfg(feature = "std")]
+    pub fn try_create_compute<F: Fn(&[T]) -> T + MaybeSend + MaybeSync + UnwindSafe + 'a>(
+        &mut self,
+        dependencies: &[CellId],
+        compute_func: F,
+    ) -> Result<ComputeCellId, CreateCellError> {
+        let (id, values) = self.prepare_compute(dependencies, None)?;
+        let values = AssertUnwindSafe(values);
+        let (val, compute_func) = panic::catch_unwind(move || {
+            let values = values;
+            (compute_func(&values.0), compute_func)
+        })
+        .map_err(|_| CreateCellError::ComputeFailed)?;
+        let func = move |v: &[T]| Ok(compute_func(v));
+        self.insert_compute(id, dependencies, val, Shared::new(func));
+        Ok(id)
+    }
+
+    // Validates `dependencies`, returning the id the new cell will get along
+    // with the current values of its dependencies.
+    fn prepare_compute(
+        &self,
+        dependencies: &[CellId],
+        fallback: Option<&T>,
+    ) -> Result<(ComputeCellId, Vec<T>), CreateCellError> {
+        // Such a cell could never update, which is almost certainly a mistake.
+        if dependencies.is_empty() {
+            return Err(CreateCellError::NoDependencies);
+        }
+        let dependencies: Vec<CellId> = dependencies.iter().map(|&d| self.backing(d)).collect();
         let mut values: Vec<T> = Vec::new();
-        for &d in _dependencies {
+        let mut seen: HashSet<CellId> = HashSet::new();
+        for &d in &dependencies {
+            if !seen.insert(d) {
+                return Err(CreateCellError::DuplicateDependency(d));
+            }
             match d {
                 CellId::Input(_) => {
                     if !self.inputs.contains_key(&d) {
-                        return Err(d);
+                        return Err(CreateCellError::NonexistentDependency(d));
                     }
                     let cell = self.inputs.get(&d).unwrap();
-                    values.push(cell.val);
+                    values.push(cell.val.clone());
                 }
                 CellId::Compute(_) => {
                     if !self.compute.contains_key(&d) {
-                        return Err(d);
+                        return Err(CreateCellError::NonexistentDependency(d));
                     }
                     let cell = self.compute.get(&d).unwrap();
-                    values.push(cell.val);
-                }
-            }
-        }
-        let id = ComputeCellId(self.compute.len());
-        self.compute.insert(CellId::Compute(id), Box::new(ComputeCell {
-            val: _compute_func(&values),
-            parents: _dependencies.to_vec(),
-            children: Vec::new(),
-            callbacks: HashMap::new(),
-            func: Box::new(_compute_func),
-            cb_id: 1,
-        }));
-        for d in _dependencies {
-            match d {
-                CellId::Input(_) => {
-                    let cell = self.inputs.get_mut(&d).unwrap();
-                    cell.children.push(CellId::Compute(id));
+                    match (&cell.error, fallback) {
+                        (None, _) => values.push(cell.val.clone()),
+                        (Some(_), Some(fallback)) => values.push(fallback.clone()),
+                        (Some(_), None) => return Err(CreateCellError::ComputeFailed),
+                    }
                 }
-                CellId::Compute(_) => {
-                    let cell = self.compute.get_mut(&d).unwrap();
-                    cell.children.push(CellId::Compute(id));
+                CellId::Constant(_) => {
+                    if !self.constants.contains_key(&d) {
+                        return Err(CreateCellError::NonexistentDependency(d));
+                    }
+                    let cell = self.constants.get(&d).unwrap();
+                    values.push(cell.val.clone());
                 }
             }
         }
-        Ok(id)
-    }
-    
-    pub fn value(&self, id: CellId) -> Option<T> {
-        match id {
-            CellId::Compute(_) => self.compute.get(&id).map(|c| c.val),
-            CellId::Input(_) => self.inputs.get(&id).map(|c| c.val)
+        let id = ComputeCellId(self.next_compute_id);
+        if let Some(path) = self.find_cycle(CellId::Compute(id), &dependencies) {
+            return Err(CreateCellError::Cycle(path));
         }
+        Ok((id, values))
     }
-    
-    pub fn set_value(&mut self, _id: InputCellId, _new_value: T) -> bool {
-        let _id = CellId::Input(_id);
-        if !self.inputs.contains_key(&_id) {
-            return false;
-        }
-        
-        if let Some(c) = self.inputs.get_mut(&_id) { 
-            c.val = _new_value;
-            let mut topo: Vec<CellId> = Vec::new();
-            let mut seen: HashSet<CellId> = HashSet::new();
-            self.get_topo_order(_id, &mut topo, &mut seen);
-            
-            topo.reverse();
-            self.update_compute_cell_value(&topo[1..]);
+
+    fn insert_compute(
+        &mut self,
+        id: ComputeCellId,
+        dependencies: &[CellId],
+        val: T,
+        func: ComputeFn<'a, T>,
+    ) {
+        let dependencies: Vec<CellId> = dependencies.iter().map(|&d| self.backing(d)).collect();
+        self.next_compute_id = self.next_compute_id.max(id.0 + 1);
+        self.compute.insert(CellId::Compute(id), Box::new(ComputeCell {
+            val,
+            parents: dependencies.clone(),
+            children: Vec::new(),
+            callbacks: CallbackList::new(),
+            func,
+            preview: None,
+            error: None,
+            changed_at: self.generation,
+            frozen: false,
+            history: None,
+            label: None,
+            fallback: None,
+            recomputes: 0,
+        }));
+        for &d in &dependencies {
+            self.children_of_mut(d).unwrap().push(CellId::Compute(id));
         }
-        true
     }
     
-    fn get_topo_order(&mut self, id: CellId, stack: &mut Vec<CellId>, seen: &mut HashSet<CellId>) {
-        seen.insert(id);
-        let children = match id {
-            CellId::Input(_) => {self.inputs.get_mut(&id).unwrap().children.clone()}
-            CellId::Compute(_) => {self.compute.get_mut(&id).unwrap().children.clone()}
-        };
-        for cid in children {
-            if seen.contains(&cid) {
-                continue;
+    // The cache lives inside the closure stored on the cell, keeping the most
+    // recently used `cache_size` dependency vectors and their results.
+    // `would_change` reads the cache without adding to it or counting as a
+    // use, so previews can't evict real entries.
+    pub fn create_memo_compute<F: Fn(&[T]) -> T + MaybeSend + MaybeSync + 'a>(
+        &mut self,
+        dependencies: &[CellId],
+        cache_size: usize,
+        compute_func: F,
+    ) -> Result<ComputeCellId, CreateCellError>
+    where
+        T: Hash + Eq + MaybeSend + 'a,
+    {
+        let cache = (HashMap::<Vec<T>, T>::new(), VecDeque::<Vec<T>>::new());
+        let cache = Shared::new(Lock::new(cache));
+        let compute_func = Shared::new(compute_func);
+        let (lookup, evaluate) = (Shared::clone(&cache), Shared::clone(&compute_func));
+        let id = self.create_compute(dependencies, move |values: &[T]| {
+            let mut guard = cache.borrow_mut();
+            let (results, order) = &mut *guard;
+            if let Some(hit) = results.get(values) {
+                let pos = order.iter().position(|k| k.as_slice() == values).unwrap();
+                let key = order.remove(pos).unwrap();
+                order.push_back(key);
+                return hit.clone();
             }
-            self.get_topo_order(cid, stack, seen);
-        }
-        stack.push(id);
-    }
-
-    fn update_compute_cell_value(&mut self, queue: &[CellId]) {
-        for cell_id in queue {
-            let parent_values: Vec<T> = self
-                .compute
-                .get(cell_id)
-                .unwrap()
-                .parents
-                .iter()
-                .map(|par| self.value(*par).unwrap())
-                .collect();
-            
-            let cell = self.compute.get_mut(cell_id).unwrap();
-            let new_val = (cell.func)(&parent_values);
 
-            if new_val != cell.val {
-                cell.val = new_val;
-                for cb in cell.callbacks.values_mut() {
-                    (cb)(new_val);    
+            let result = compute_func(values);
+            if cache_size > 0 {
+                if order.len() == cache_size {
+                    let evicted = order.pop_front().unwrap();
+                    results.remove(&evicted);
                 }
+                order.push_back(values.to_vec());
+                results.insert(values.to_vec(), result.clone());
             }
-        }
+            result
+        })?;
+        let preview = move |values: &[T]| {
+            let hit = lookup.borrow_mut().0.get(values).cloned();
+            Ok(hit.unwrap_or_else(|| evaluate(values)))
+        };
+        self.compute.get_mut(&CellId::Compute(id)).unwrap().preview = Some(Shared::new(preview));
+        Ok(id)
     }
-    
-    pub fn add_callback<F: 'a + FnMut(T)>(
+
+    pub fn create_sum(
         &mut self,
-        _id: ComputeCellId,
-        _callback: F,
-    ) -> Option<CallbackId> {
-        match self.compute.get_mut(&CellId::Compute(_id)) {
-            None => None,
-            Some(cell) => {
-                let id = CallbackId(cell.cb_id);
-                cell.cb_id += 1;
-                cell.callbacks.insert(id, Box::new(_callback));
-                Some(id)
-            }
-        }
+        dependencies: &[CellId],
+    ) -> Result<ComputeCellId, CreateCellError>
+    where
+        T: Sum,
+    {
+        self.create_compute(dependencies, |v| v.iter().cloned().sum())
     }
-    
+
+    pub fn create_product(
+        &mut self,
+        dependencies: &[CellId],
+    ) -> Result<ComputeCellId, CreateCellError>
+    where
+        T: Product,
+    {
+        self.create_compute(dependencies, |v| v.iter().cloned().product())
+    }
+
+    // Reduces the dependency values left to right, starting from a fresh clone
+    // of `seed` every time the cell is recomputed.
+    pub fn create_fold<F: Fn(T, T) -> T + MaybeSend + MaybeSync + 'a>(
+        &mut self,
+        dependencies: &[CellId],
+        seed: T,
+        f: F,
+    ) -> Result<ComputeCellId, CreateCellError>
+    where
+        T: MaybeSend + MaybeSync + 'a,
+    {
+        self.create_compute(dependencies, move |v| v.iter().cloned().fold(seed.clone(), &f))
+    }
+
+    // There is no seed to fold from, but a compute cell always has at least
+    // one dependency, so the extremes are always defined.
+    pub fn create_min(
+        &mut self,
+        dependencies: &[CellId],
+    ) -> Result<ComputeCellId, CreateCellError>
+    where
+        T: Ord,
+    {
+        self.create_compute(dependencies, |v| v.iter().min().unwrap().clone())
+    }
+
+    pub fn create_max(
+        &mut self,
+        dependencies: &[CellId],
+    ) -> Result<ComputeCellId, CreateCellError>
+    where
+        T: Ord,
+    {
+        self.create_compute(dependencies, |v| v.iter().max().unwrap().clone())
+    }
+
+    // Follows `if_true` while `cond` converts to `true` and `if_false`
+    // otherwise. The cell depends on all three, so it is recomputed when any
+    // of them changes, but only a change to the chosen side (or to `cond`)
+    // can change its value. Like any dependencies, the three must differ.
+    pub fn create_select(
+        &mut self,
+        cond: CellId,
+        if_true: CellId,
+        if_false: CellId,
+    ) -> Result<ComputeCellId, CreateCellError>
+    where
+        T: Into<bool>,
+    {
+        self.create_compute(&[cond, if_true, if_false], |v| {
+            if v[0].clone().into() {
+                v[1].clone()
+            } else {
+                v[2].clone()
+            }
+        })
+    }
+
+    // Fixed-arity variants of `create_compute` for closures that would rather
+    // take their arguments by value than index into a slice. Up to three
+    // dependency values are passed without allocating.
+    pub fn create_compute2<F: Fn(T, T) -> T + MaybeSend + MaybeSync + 'a>(
+        &mut self,
+        a: CellId,
+        b: CellId,
+        f: F,
+    ) -> Result<ComputeCellId, CreateCellError> {
+        self.map2(a, b, f)
+    }
+
+    pub fn create_compute3<F: Fn(T, T, T) -> T + MaybeSend + MaybeSync + 'a>(
+        &mut self,
+        a: CellId,
+        b: CellId,
+        c: CellId,
+        f: F,
+    ) -> Result<ComputeCellId, CreateCellError> {
+        self.create_compute(&[a, b, c], move |v| f(v[0].clone(), v[1].clone(), v[2].clone()))
+    }
+
+    // Evaluates `dependencies` with whichever closure is keyed by the current
+    // value of `mode`, or with the first closure if none is, so `funcs` can't
+    // be empty. `mode` is an extra dependency, not passed to the closures, so
+    // switching recomputes the cell too.
+    pub fn create_switching_compute(
+        &mut self,
+        mode: CellId,
+        funcs: Vec<(T, BoxedComputeFn<'a, T>)>,
+        dependencies: &[CellId],
+    ) -> Result<ComputeCellId, CreateCellError>
+    where
+        T: Hash + Eq + MaybeSend + MaybeSync + 'a,
+    {
+        let Some((default, _)) = funcs.first() else {
+            return Err(CreateCellError::NoFunctions);
+        };
+        let default = default.clone();
+        let funcs: HashMap<T, BoxedComputeFn<'a, T>> = funcs.into_iter().collect();
+        let mut all = vec![mode];
+        all.extend_from_slice(dependencies);
+        self.create_compute(&all, move |v| {
+            let func = funcs.get(&v[0]).unwrap_or_else(|| &funcs[&default]);
+            func(&v[1..])
+        })
+    }
+
+    pub fn map<F: Fn(T) -> T + MaybeSend + MaybeSync + 'a>(
+        &mut self,
+        src: CellId,
+        f: F,
+    ) -> Result<ComputeCellId, CreateCellError> {
+        self.create_compute(&[src], move |v| f(v[0].clone()))
+    }
+
+    pub fn map2<F: Fn(T, T) -> T + MaybeSend + MaybeSync + 'a>(
+        &mut self,
+        a: CellId,
+        b: CellId,
+        f: F,
+    ) -> Result<ComputeCellId, CreateCellError> {
+        self.create_compute(&[a, b], move |v| f(v[0].clone(), v[1].clone()))
+    }
+
+    // Walks the transitive dependencies of `deps` looking for `target`. If it
+    // is reachable, returns the path `target -> deps[i] -> ... -> target`.
+    fn find_cycle(&self, target: CellId, deps: &[CellId]) -> Option<Vec<CellId>> {
+        // A cell that isn't in the graph can't be anyone's dependency yet.
+        if !self.compute.contains_key(&target) {
+            return None;
+        }
+        let mut came_from: HashMap<CellId, CellId> = HashMap::new();
+        let mut stack: Vec<CellId> = Vec::new();
+        for &d in deps {
+            if let Entry::Vacant(e) = came_from.entry(d) {
+                e.insert(target);
+                stack.push(d);
+            }
+        }
+
+        while let Some(id) = stack.pop() {
+            if id == target {
+                let mut path = vec![target];
+                let mut cur = target;
+                loop {
+                    cur = came_from[&cur];
+                    path.push(cur);
+                    if cur == target {
+                        break;
+                    }
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for &p in self.parents_of(id) {
+                if let Entry::Vacant(e) = came_from.entry(p) {
+                    e.insert(id);
+                    stack.push(p);
+                }
+            }
+        }
+        None
+    }
+
+    // The id of the cell `id` names, which differs from `id` only for aliases.
+    fn backing(&self, id: CellId) -> CellId {
+        match id {
+            CellId::Input(input) => CellId::Input(*self.aliases.get(&input).unwrap_or(&input)),
+            _ => id,
+        }
+    }
+
+    fn children_of(&self, id: CellId) -> Option<&Vec<CellId>> {
+        match id {
+            CellId::Input(_) => self.inputs.get(&id).map(|c| &c.children),
+            CellId::Compute(_) => self.compute.get(&id).map(|c| &c.children),
+            CellId::Constant(_) => self.constants.get(&id).map(|c| &c.children),
+        }
+    }
+
+    fn children_of_mut(&mut self, id: CellId) -> Option<&mut Vec<CellId>> {
+        match id {
+            CellId::Input(_) => self.inputs.get_mut(&id).map(|c| &mut c.children),
+            CellId::Compute(_) => self.compute.get_mut(&id).map(|c| &mut c.children),
+            CellId::Constant(_) => self.constants.get_mut(&id).map(|c| &mut c.children),
+        }
+    }
+
+    fn parents_of(&self, id: CellId) -> &[CellId] {
+        match self.compute.get(&id) {
+            Some(cell) => &cell.parents,
+            None => &[],
+        }
+    }
+
+    pub fn value(&self, id: CellId) -> Option<T> {
+        self.value_ref(id).cloned()
+    }
+
+    // For callers to whom an unknown id is a bug: panics, naming the id and
+    // the caller's location, instead of returning `None`. An errored compute
+    // cell still exists, so it panics with a different message.
+    #[track_caller]
+    pub fn value_expect(&self, id: CellId) -> T {
+        if self.kind(id).is_none() {
+            panic!("cell {} does not exist", node_name(id));
+        }
+        match self.value(id) {
+            Some(val) => val,
+            None => panic!("cell {} failed to compute", node_name(id)),
+        }
+    }
+
+    pub fn value_ref(&self, id: CellId) -> Option<&T> {
+        let id = self.backing(id);
+        match id {
+            CellId::Compute(_) => self
+                .compute
+                .get(&id)
+                .filter(|c| c.error.is_none())
+                .map(|c| &c.val),
+            CellId::Input(_) => self.inputs.get(&id).map(|c| &c.val),
+            CellId::Constant(_) => self.constants.get(&id).map(|c| &c.val),
+        }
+    }
+
+    pub fn value_or_default(&self, id: CellId) -> T
+    where
+        T: Default,
+    {
+        self.value(id).unwrap_or_default()
+    }
+    
+    pub fn iter_cells(&self) -> impl Iterator<Item = (CellId, T)> + use<'_, 'a, T> {
+        let inputs = self.inputs.iter().map(|(id, c)| (*id, c.val.clone()));
+        let constants = self.constants.iter().map(|(id, c)| (*id, c.val.clone()));
+        let compute = self
+            .compute
+            .iter()
+            .filter(|(_, c)| c.error.is_none())
+            .map(|(id, c)| (*id, c.val.clone()));
+        inputs.chain(constants).chain(compute)
+    }
+
+    // `iter_cells` collected into a map, for lookups and comparisons.
+    pub fn all_values(&self) -> HashMap<CellId, T> {
+        self.iter_cells().collect()
+    }
+
+    // Checks that every `parents` entry is mirrored by a `children` entry on
+    // the parent and vice versa, and that nothing refers to a missing cell.
+    pub fn validate(&self) -> Result<(), GraphError> {
+        for (&id, cell) in &self.compute {
+            for &parent in &cell.parents {
+                match self.children_of(parent) {
+                    None => return Err(GraphError::DanglingReference { from: id, to: parent }),
+                    Some(children) if !children.contains(&id) => {
+                        return Err(GraphError::MissingChildLink { parent, child: id })
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        let ids = self.inputs.keys().chain(self.constants.keys()).chain(self.compute.keys());
+        for &id in ids {
+            for &child in self.children_of(id).unwrap() {
+                match self.compute.get(&child) {
+                    None => return Err(GraphError::DanglingReference { from: id, to: child }),
+                    Some(cell) if !cell.parents.contains(&id) => {
+                        return Err(GraphError::MissingParentLink { parent: id, child })
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Ids are only meaningful within one reactor, so this is only useful when
+    // both were built by the same sequence of `create_*` calls. Callbacks are
+    // ignored; a cell in an error state equals one in the same state.
+    pub fn values_equal(&self, other: &Reactor<'_, T>) -> bool {
+        let same_ids = self.inputs.len() == other.inputs.len()
+            && self.compute.len() == other.compute.len()
+            && self.constants.len() == other.constants.len();
+        same_ids
+            && self
+                .inputs
+                .keys()
+                .chain(self.compute.keys())
+                .chain(self.constants.keys())
+                .all(|&id| other.kind(id).is_some() && self.value_ref(id) == other.value_ref(id))
+    }
+
+    pub fn input_count(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn compute_count(&self) -> usize {
+        self.compute.len()
+    }
+
+    // `None` for ids this reactor doesn't hold, whatever their variant.
+    pub fn kind(&self, id: CellId) -> Option<CellKind> {
+        let id = self.backing(id);
+        let exists = match id {
+            CellId::Input(_) => self.inputs.contains_key(&id),
+            CellId::Compute(_) => self.compute.contains_key(&id),
+            CellId::Constant(_) => self.constants.contains_key(&id),
+        };
+        exists.then_some(match id {
+            CellId::Input(_) => CellKind::Input,
+            CellId::Compute(_) => CellKind::Compute,
+            CellId::Constant(_) => CellKind::Constant,
+        })
+    }
+
+    pub fn is_input(&self, id: CellId) -> bool {
+        self.kind(id) == Some(CellKind::Input)
+    }
+
+    pub fn is_compute(&self, id: CellId) -> bool {
+        self.kind(id) == Some(CellKind::Compute)
+    }
+
+    pub fn dependencies(&self, id: CellId) -> Option<Vec<CellId>> {
+        let id = self.backing(id);
+        match id {
+            CellId::Compute(_) => self.compute.get(&id).map(|c| c.parents.clone()),
+            CellId::Input(_) => self.inputs.get(&id).map(|_| Vec::new()),
+            CellId::Constant(_) => self.constants.get(&id).map(|_| Vec::new()),
+        }
+    }
+
+    pub fn dependents(&self, id: CellId) -> Option<Vec<CellId>> {
+        let id = self.backing(id);
+        self.children_of(id).cloned()
+    }
+
+    // Compute cells nothing depends on, in no particular order. Cells read by
+    // a `create_compute_into` cell aren't leaves, just as `remove_cell`
+    // refuses to remove them.
+    pub fn leaf_cells(&self) -> Vec<ComputeCellId> {
+        self.compute_ids_where(|id, cell| !self.has_dependents(id, cell))
+    }
+
+    // Leaf cells that no callback is watching either, so removing them would
+    // go unnoticed.
+    pub fn dead_cells(&self) -> Vec<ComputeCellId> {
+        self.compute_ids_where(|id, cell| {
+            !self.has_dependents(id, cell) && cell.callbacks.len() == 0
+        })
+    }
+
+    fn has_dependents(&self, id: CellId, cell: &ComputeCell<'a, T>) -> bool {
+        !cell.children.is_empty() || self.typed.values().any(|c| c.parents.contains(&id))
+    }
+
+    fn compute_ids_where<F>(&self, f: F) -> Vec<ComputeCellId>
+    where
+        F: Fn(CellId, &ComputeCell<'a, T>) -> bool,
+    {
+        self.compute
+            .iter()
+            .filter(|(id, cell)| f(**id, cell))
+            .filter_map(|(id, _)| match id {
+                CellId::Compute(id) => Some(*id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn cell_info(&self, id: CellId) -> Option<CellInfo<T>> {
+        let id = self.backing(id);
+        let callback_count = match id {
+            CellId::Input(_) => self.inputs.get(&id)?.callbacks.len(),
+            CellId::Compute(_) => self.compute.get(&id)?.callbacks.len(),
+            CellId::Constant(_) => self.constants.get(&id).map(|_| 0)?,
+        };
+        Some(CellInfo {
+            value: self.value(id),
+            parents: self.parents_of(id).to_vec(),
+            children: self.children_of(id)?.clone(),
+            callback_count,
+        })
+    }
+
+    // The length of the longest path from an input or constant to `id`, so
+    // inputs and constants sit at depth 0.
+    pub fn depth(&self, id: CellId) -> Option<usize> {
+        self.kind(id)?;
+        let mut depths: HashMap<CellId, usize> = HashMap::new();
+        let mut work: Vec<Visit> = vec![Visit::Enter(id)];
+        while let Some(visit) = work.pop() {
+            match visit {
+                Visit::Enter(id) => {
+                    if depths.contains_key(&id) {
+                        continue;
+                    }
+                    work.push(Visit::Exit(id));
+                    work.extend(self.parents_of(id).iter().map(|&p| Visit::Enter(p)));
+                }
+                Visit::Exit(id) => {
+                    let depth = self.parents_of(id).iter().map(|p| depths[p] + 1).max();
+                    depths.insert(id, depth.unwrap_or(0));
+                }
+            }
+        }
+        Some(depths[&id])
+    }
+
+    // The compute cells a write to `id` would reach, parents first. An
+    // unknown input affects nothing.
+    pub fn affected_cells(&self, id: InputCellId) -> Vec<ComputeCellId> {
+        let id = self.backing(CellId::Input(id));
+        if !self.inputs.contains_key(&id) {
+            return Vec::new();
+        }
+
+        let mut topo: Vec<CellId> = Vec::new();
+        self.get_topo_order(id, &mut topo, &mut HashSet::new());
+        topo.iter()
+            .rev()
+            .filter_map(|c| match c {
+                CellId::Compute(c) => Some(*c),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // The compute cells whose value would differ after `set_value(id,
+    // new_value)`, parents first. The affected closures run against scratch
+    // values, so no cell changes and no callback fires. Memo cells only read
+    // their cache; other closures that keep state of their own do see the
+    // calls.
+    pub fn would_change(&self, id: InputCellId, new_value: T) -> Vec<ComputeCellId> {
+        let input = self.backing(CellId::Input(id));
+        let Some(new_value) = self.inputs.get(&input).and_then(|c| c.validate(new_value)) else {
+            return Vec::new();
+        };
+        if self.inputs[&input].val == new_value {
+            return Vec::new();
+        }
+
+        let mut scratch: HashMap<CellId, Result<T, CellError>> = HashMap::new();
+        scratch.insert(input, Ok(new_value));
+        let mut changed: Vec<ComputeCellId> = Vec::new();
+        for cell_id in self.affected_cells(id) {
+            let id = CellId::Compute(cell_id);
+            let cell = &self.compute[&id];
+            if cell.frozen || !cell.parents.iter().any(|p| scratch.contains_key(p)) {
+                continue;
+            }
+            let result = cell
+                .parents
+                .iter()
+                .map(|p| match (scratch.get(p), &cell.fallback) {
+                    (Some(Err(_)), Some(fallback)) => Ok(fallback.clone()),
+                    (Some(val), _) => val.clone(),
+                    (None, fallback) => self.parent_value(p, fallback.as_ref()),
+                })
+                .collect::<Result<Vec<T>, CellError>>()
+                .and_then(|values| (cell.preview.as_ref().unwrap_or(&cell.func))(&values));
+            let differs = match (&result, &cell.error) {
+                (Ok(val), None) => *val != cell.val,
+                (Err(_), Some(_)) => false,
+                _ => true,
+            };
+            if differs {
+                scratch.insert(id, result);
+                changed.push(cell_id);
+            }
+        }
+        changed
+    }
+
+    // The compute cells `set_value_batch(updates)` may recompute, in the
+    // order it would recompute them. Nothing is evaluated, so this is an
+    // upper bound: a cell whose parents all end up unchanged is skipped when
+    // the batch runs. Writes of the value an input already has are left out.
+    // Empty if the batch would be refused.
+    pub fn plan_batch(&self, updates: &[(InputCellId, T)]) -> Vec<ComputeCellId> {
+        let mut sources: Vec<CellId> = Vec::new();
+        for (id, val) in updates {
+            let id = self.backing(CellId::Input(*id));
+            let Some(cell) = self.inputs.get(&id) else {
+                return Vec::new();
+            };
+            match cell.validate(val.clone()) {
+                Some(val) if val != cell.val => sources.push(id),
+                Some(_) => {}
+                None => return Vec::new(),
+            }
+        }
+
+        let mut topo: Vec<CellId> = Vec::new();
+        let mut seen: HashSet<CellId> = HashSet::new();
+        for &id in &sources {
+            self.get_topo_order(id, &mut topo, &mut seen);
+        }
+        // Frozen cells are skipped, and so are cells only reachable through
+        // them.
+        let mut planned: HashSet<CellId> = sources.into_iter().collect();
+        let mut plan: Vec<ComputeCellId> = Vec::new();
+        for &id in topo.iter().rev() {
+            let (CellId::Compute(cell_id), Some(cell)) = (id, self.compute.get(&id)) else {
+                continue;
+            };
+            if !cell.frozen && cell.parents.iter().any(|p| planned.contains(p)) {
+                planned.insert(id);
+                plan.push(cell_id);
+            }
+        }
+        plan
+    }
+
+    pub fn set_value(&mut self, _id: InputCellId, _new_value: T) -> bool {
+        self.set_value_changed(_id, _new_value).is_some()
+    }
+
+    // Like `set_value`, but panics if the input doesn't exist. Writes refused
+    // for any other reason still return `false`.
+    #[track_caller]
+    pub fn set_value_expect(&mut self, id: InputCellId, new_value: T) -> bool {
+        match self.try_set_value(id, new_value) {
+            Err(PropagationError::NonexistentCell) => {
+                panic!("cell {} does not exist", node_name(CellId::Input(id)))
+            }
+            result => result.is_ok(),
+        }
+    }
+
+    // Writing the value an input already holds is a no-op and skips the
+    // propagation entirely.
+    pub fn set_value_changed(&mut self, id: InputCellId, new_value: T) -> Option<bool> {
+        self.try_set_value(id, new_value).ok()
+    }
+
+    // Like `set_value_changed`, but says why a write was refused. A write
+    // that would exceed the step limit is undone and nothing is recomputed.
+    pub fn try_set_value(
+        &mut self,
+        id: InputCellId,
+        new_value: T,
+    ) -> Result<bool, PropagationError> {
+        let input = CellId::Input(id);
+        let id = self.backing(input);
+        let cell = self.inputs.get(&id).ok_or(PropagationError::NonexistentCell)?;
+        let new_value = cell.validate(new_value).ok_or(PropagationError::Rejected)?;
+        if let Some(frame) = &mut self.frame {
+            return Ok(frame_write(frame, id, &cell.val, new_value));
+        }
+        if cell.val == new_value {
+            return Ok(false);
+        }
+
+        self.write_input(input, new_value);
+        self.propagate(&[id])?;
+        Ok(true)
+    }
+
+    // Mutates the input in place instead of building a replacement value, then
+    // propagates if that left it different from before.
+    pub fn modify_input<F: FnOnce(&mut T)>(&mut self, id: InputCellId, f: F) -> bool {
+        let input = CellId::Input(id);
+        let id = self.backing(input);
+        let Some(cell) = self.inputs.get_mut(&id) else {
+            return false;
+        };
+        // In a frame, `f` builds on the value the input is going to end up
+        // with, which may not have been written to the cell yet.
+        if let Some(frame) = &mut self.frame {
+            let pending = frame.iter().find(|(i, _)| *i == id).map(|(_, val)| val);
+            let before = pending.unwrap_or(&cell.val).clone();
+            let mut after = before.clone();
+            f(&mut after);
+            if after == before {
+                return true;
+            }
+            let Some(after) = cell.validate(after) else {
+                return false;
+            };
+            frame_write(frame, id, &cell.val, after);
+            return true;
+        }
+        let before = cell.val.clone();
+        f(&mut cell.val);
+        if cell.val == before {
+            return true;
+        }
+
+        // Put the old value back so `write_input` sees a normal write.
+        let after = mem::replace(&mut cell.val, before);
+        let Some(after) = cell.validate(after) else {
+            return false;
+        };
+        self.write_input(input, after);
+        self.propagate(&[id]).is_ok()
+    }
+
+    // Caps how many compute cells a single propagation may visit, as a guard
+    // against runaway graphs. `None`, the default, means no limit.
+    pub fn set_max_propagation_steps(&mut self, limit: Option<usize>) {
+        self.max_propagation_steps = limit;
+    }
+
+    // The most callbacks a single cell may have; adding more fails with
+    // `AddCallbackError::LimitReached`. Cells already over a lowered limit
+    // keep their callbacks. Unlimited by default.
+    pub fn set_callback_limit(&mut self, limit: usize) {
+        self.callback_limit = limit;
+    }
+
+    // Nothing is written unless every input exists and accepts its value.
+    pub fn set_value_batch(&mut self, updates: &[(InputCellId, T)]) -> bool {
+        let mut writes: Vec<(CellId, CellId, T)> = Vec::new();
+        for (id, val) in updates {
+            let input = CellId::Input(*id);
+            let id = self.backing(input);
+            match self.inputs.get(&id).and_then(|cell| cell.validate(val.clone())) {
+                Some(val) => writes.push((input, id, val)),
+                None => return false,
+            }
+        }
+        if let Some(frame) = &mut self.frame {
+            for (_, id, val) in writes {
+                frame_write(frame, id, &self.inputs[&id].val, val);
+            }
+            return true;
+        }
+
+        let mut sources: Vec<CellId> = Vec::new();
+        for (input, id, val) in writes {
+            self.write_input(input, val);
+            sources.push(id);
+        }
+        self.propagate(&sources).is_ok()
+    }
+
+    // Runs every later write to the input through `validator`, which returns
+    // the value to store, possibly clamped, or `None` to refuse the write.
+    // Refused writes change nothing and make `set_value` return `false`. The
+    // current value is left as it is, and resets and rollbacks bypass it.
+    pub fn set_input_validator<F: Fn(T) -> Option<T> + MaybeSend + MaybeSync + 'a>(
+        &mut self,
+        id: InputCellId,
+        validator: F,
+    ) -> bool {
+        match self.inputs.get_mut(&self.backing(CellId::Input(id))) {
+            Some(cell) => {
+                cell.validator = Some(Shared::new(validator));
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Also the way back after a panic in a callback or compute closure was
+    // caught mid-propagation: every cell is recomputed from the inputs as they
+    // are, after which writes are accepted again.
+    pub fn recompute_all(&mut self) -> Result<(), PropagationError> {
+        self.propagating = false;
+        let sources: Vec<CellId> = self.compute.keys().copied().collect();
+        self.propagate(&sources)
+    }
+
+    // Recomputes the cell even though none of its dependencies changed, for
+    // closures that read state the reactor can't see, and then whichever of
+    // its descendants that affects.
+    pub fn invalidate(&mut self, id: ComputeCellId) -> bool {
+        let id = CellId::Compute(id);
+        if !self.compute.contains_key(&id) {
+            return false;
+        }
+        self.propagate(&[id]).is_ok()
+    }
+
+    // Keeps the cell's id, dependencies and callbacks; only the closure
+    // changes, and the new one takes effect straight away. If the cell can't
+    // be recomputed, say over the step limit, the old closure stays and this
+    // returns `false`.
+    pub fn replace_compute_func<F: Fn(&[T]) -> T + MaybeSend + MaybeSync + 'a>(
+        &mut self,
+        id: ComputeCellId,
+        compute_func: F,
+    ) -> bool {
+        let id = CellId::Compute(id);
+        let Some(cell) = self.compute.get_mut(&id) else {
+            return false;
+        };
+        let old = mem::replace(&mut cell.func, Shared::new(move |v: &[T]| Ok(compute_func(v))));
+        let old_preview = cell.preview.take();
+        if self.propagate(&[id]).is_err() {
+            let cell = self.compute.get_mut(&id).unwrap();
+            (cell.func, cell.preview) = (old, old_preview);
+            return false;
+        }
+        true
+    }
+
+    // Like `replace_compute_func`, for the compute cell labelled `label`.
+    // Labels needn't be unique, so this fails unless exactly one has it.
+    pub fn replace_compute_func_by_label<F: Fn(&[T]) -> T + MaybeSend + MaybeSync + 'a>(
+        &mut self,
+        label: &str,
+        compute_func: F,
+    ) -> bool {
+        let ids = self.compute_ids_where(|_, cell| cell.label.as_deref() == Some(label));
+        match ids[..] {
+            [id] => self.replace_compute_func(id, compute_func),
+            _ => false,
+        }
+    }
+
+    // Like any write, a reset that can't propagate is undone.
+    pub fn reset_inputs(&mut self) -> Result<(), PropagationError> {
+        if let Some(frame) = &mut self.frame {
+            for (id, cell) in &self.inputs {
+                frame_write(frame, *id, &cell.val, cell.initial.clone());
+            }
+            return Ok(());
+        }
+        let sources: Vec<CellId> = self.inputs.keys().copied().collect();
+        for &id in &sources {
+            let initial = self.inputs.get(&id).unwrap().initial.clone();
+            self.write_input(id, initial);
+        }
+        self.propagate(&sources)
+    }
+
+    // While a transaction is open, the first write to each input remembers the
+    // value it had before the transaction so `rollback` can put it back. `id`
+    // may be an alias, which is then what callbacks report as the source.
+    fn write_input(&mut self, id: CellId, val: T) {
+        let backing = self.backing(id);
+        let written = self.written_inputs.iter().any(|(i, _)| self.backing(*i) == backing);
+        let cell = self.inputs.get_mut(&backing).unwrap();
+        if let Some(tx) = &mut self.transaction {
+            tx.original.entry(backing).or_insert_with(|| cell.val.clone());
+        }
+        if cell.val != val && !written {
+            self.written_inputs.push((id, cell.val.clone()));
+        }
+        cell.val = val;
+    }
+
+    pub fn begin_transaction(&mut self) {
+        if self.transaction.is_none() {
+            self.transaction = Some(Transaction {
+                original: HashMap::new(),
+                dirty: Vec::new(),
+            });
+        }
+    }
+
+    // The writes made inside the transaction have already reported success,
+    // so a propagation that fails here, say over the step limit, is reported
+    // by `commit` instead; the writes are undone in that case.
+    pub fn commit(&mut self) -> Result<(), PropagationError> {
+        match self.transaction.take() {
+            Some(tx) => self.propagate(&tx.dirty),
+            None => Ok(()),
+        }
+    }
+
+    pub fn rollback(&mut self) {
+        if let Some(tx) = self.transaction.take() {
+            for (id, val) in tx.original {
+                self.inputs.get_mut(&id).unwrap().val = val;
+            }
+            self.written_inputs.clear();
+        }
+    }
+
+    // Until `end_frame`, `set_value`, `set_value_batch`, `modify_input` and
+    // `reset_inputs` only note the value each input should end up with: a
+    // later write to the same input replaces an earlier one, and the cell
+    // keeps its value in the meantime. Unlike a transaction, a frame can't be
+    // rolled back.
+    pub fn begin_frame(&mut self) {
+        self.frame.get_or_insert_with(Vec::new);
+    }
+
+    // Applies the last write to each input and propagates once. If that
+    // propagation fails, every write of the frame is undone.
+    pub fn end_frame(&mut self) -> Result<(), PropagationError> {
+        let Some(writes) = self.frame.take() else {
+            return Ok(());
+        };
+        let mut sources: Vec<CellId> = Vec::new();
+        for (id, val) in writes {
+            if self.inputs[&id].val != val {
+                self.write_input(id, val);
+                sources.push(id);
+            }
+        }
+        if sources.is_empty() {
+            return Ok(());
+        }
+        self.propagate(&sources)
+    }
+
+    // Recomputes everything downstream of `sources` in a single topological
+    // pass, then fires callbacks for the cells whose value ended up different.
+    // Inside a transaction the sources are only recorded until `commit`.
+    // Fails without recomputing anything if more cells are affected than the
+    // step limit allows; pending input writes are reverted in that case.
+    fn propagate(&mut self, sources: &[CellId]) -> Result<(), PropagationError> {
+        if let Some(tx) = &mut self.transaction {
+            tx.dirty.extend_from_slice(sources);
+            return Ok(());
+        }
+        // An input that was written its own value, or was changed and then
+        // changed back, has nothing to pass on to its children.
+        let sources: Vec<CellId> =
+            sources.iter().copied().filter(|&id| self.source_changed(id)).collect();
+        let sources = sources.as_slice();
+
+        #[cfg(feature = "timing")]
+        let start = std::time::Instant::now();
+        let mut topo: Vec<CellId> = Vec::new();
+        let mut seen: HashSet<CellId> = HashSet::new();
+        for &id in sources {
+            if !seen.contains(&id) {
+                self.get_topo_order(id, &mut topo, &mut seen);
+            }
+        }
+
+        topo.reverse();
+        topo.retain(|id| matches!(id, CellId::Compute(_)));
+        if self.max_propagation_steps.is_some_and(|limit| topo.len() > limit) {
+            self.revert_written_inputs();
+            return Err(PropagationError::StepLimitExceeded);
+        }
+        // `&mut self` keeps callbacks from reaching back into the reactor, so
+        // the flag can only still be set if a callback or compute closure
+        // panicked and the panic was caught. The graph is then half updated,
+        // and rather than propagate on top of it every later write is
+        // rejected and rolled back until `recompute_all` resyncs the graph.
+        if self.propagating {
+            self.revert_written_inputs();
+            return Err(PropagationError::Reentrant);
+        }
+        self.propagating = true;
+        #[cfg(debug_assertions)]
+        self.recomputed.clear();
+        let before = self.compute_values(&topo);
+        self.update_compute_cell_value(&topo, sources);
+        if !self.typed.is_empty() {
+            self.refresh_typed(&sources.iter().chain(&topo).copied().collect());
+        }
+        let (changed, callbacks_fired) = self.fire_callbacks(&before);
+        self.propagating = false;
+        self.metrics = Some(PropagationMetrics {
+            visited: topo.len(),
+            changed,
+            callbacks_fired,
+            #[cfg(feature = "timing")]
+            elapsed: start.elapsed(),
+        });
+        Ok(())
+    }
+
+    fn source_changed(&self, id: CellId) -> bool {
+        match id {
+            CellId::Input(_) => self.written_inputs.iter().any(|(written, old)| {
+                self.backing(*written) == id && self.inputs[&id].val != *old
+            }),
+            _ => true,
+        }
+    }
+
+    fn revert_written_inputs(&mut self) {
+        for (id, old_val) in mem::take(&mut self.written_inputs) {
+            let id = self.backing(id);
+            self.inputs.get_mut(&id).unwrap().val = old_val;
+        }
+    }
+
+    // Statistics for the most recent propagation, or `None` if nothing has
+    // propagated yet. Writes made inside a transaction are counted together
+    // when it commits.
+    pub fn last_propagation_metrics(&self) -> Option<&PropagationMetrics> {
+        self.metrics.as_ref()
+    }
+    
+    // Every cell, with each parent ahead of its children. Starting from the
+    // inputs and constants reaches every compute cell, since none of them can
+    // be created without dependencies; the compute cells are only walked in
+    // case the graph was left inconsistent.
+    pub fn topological_order(&self) -> Vec<CellId> {
+        let mut topo: Vec<CellId> = Vec::new();
+        let mut seen: HashSet<CellId> = HashSet::new();
+        let ids = self.inputs.keys().chain(self.constants.keys()).chain(self.compute.keys());
+        for &id in ids {
+            if !seen.contains(&id) {
+                self.get_topo_order(id, &mut topo, &mut seen);
+            }
+        }
+        topo.reverse();
+        topo
+    }
+
+    // Calls `visitor` with every cell and its parents, parents first, so an
+    // analysis can fold over the graph without sorting it itself.
+    pub fn visit_topological<V: FnMut(CellId, &[CellId])>(&self, mut visitor: V) {
+        for id in self.topological_order() {
+            visitor(id, self.parents_of(id));
+        }
+    }
+
+    // Post-order DFS over `children`, run on an explicit work stack so deep
+    // chains don't overflow the call stack. `Enter` marks a cell still to be
+    // expanded, `Exit` marks one whose children have all been finished.
+    fn get_topo_order(&self, id: CellId, stack: &mut Vec<CellId>, seen: &mut HashSet<CellId>) {
+        let mut work: Vec<Visit> = vec![Visit::Enter(id)];
+        while let Some(visit) = work.pop() {
+            match visit {
+                Visit::Enter(id) => {
+                    if !seen.insert(id) {
+                        continue;
+                    }
+                    work.push(Visit::Exit(id));
+                    let children = self.children_of(id).unwrap();
+                    work.extend(children.iter().rev().map(|&cid| Visit::Enter(cid)));
+                }
+                Visit::Exit(id) => stack.push(id),
+            }
+        }
+    }
+
+    // A work queue over dirty cells, popped in `queue` order so every parent
+    // taking part in the propagation settles before its children. Compute
+    // sources and the children of written inputs start out dirty; after that
+    // a child is only marked dirty once a parent's value actually changed, so
+    // cells whose parents all held still are never looked at. Cells outside
+    // `queue` are left alone.
+    fn update_compute_cell_value(&mut self, queue: &[CellId], sources: &[CellId]) {
+        let mut dirty = DirtyQueue::new(queue);
+        for &source in sources {
+            match source {
+                CellId::Compute(_) => dirty.mark(source),
+                _ => {
+                    for &child in self.children_of(source).into_iter().flatten() {
+                        dirty.mark(child);
+                    }
+                }
+            }
+        }
+
+        while let Some(cell_id) = dirty.pop() {
+            let parents = &self.compute.get(&cell_id).unwrap().parents;
+            // Glitch freedom: a cell must not be visited before every parent
+            // that takes part in this propagation has settled.
+            debug_assert!(
+                parents.iter().all(|p| !dirty.is_pending(p)),
+                "{cell_id:?} was recomputed before its parents settled"
+            );
+            if self.compute.get(&cell_id).unwrap().frozen {
+                continue;
+            }
+            let parent_values = self.parent_values(self.compute.get(&cell_id).unwrap());
+
+            let cell = self.compute.get_mut(&cell_id).unwrap();
+            let result = parent_values.and_then(|values| {
+                cell.recomputes += 1;
+                #[cfg(debug_assertions)]
+                {
+                    *self.recomputed.entry(cell_id).or_insert(0) += 1;
+                }
+                (cell.func)(&values)
+            });
+            let changed = match result {
+                Ok(new_val) => {
+                    let recovered = cell.error.take().is_some();
+                    let changed = recovered || new_val != cell.val;
+                    if changed {
+                        cell.val = new_val;
+                    }
+                    changed
+                }
+                Err(e) => {
+                    cell.error = Some(e);
+                    true
+                }
+            };
+            if changed {
+                for &child in &cell.children {
+                    dirty.mark(child);
+                }
+            }
+        }
+    }
+
+    // The values of the cell's parents, or the error of the first one that
+    // failed and has no fallback to stand in for it.
+    fn parent_values(&self, cell: &ComputeCell<'a, T>) -> Result<ParentValues<T>, CellError> {
+        let value = |par: &CellId| self.parent_value(par, cell.fallback.as_ref());
+        let parents = cell.parents.as_slice();
+        Ok(match parents {
+            [a] => ParentValues::One([value(a)?]),
+            [a, b] => ParentValues::Two([value(a)?, value(b)?]),
+            [a, b, c] => ParentValues::Three([value(a)?, value(b)?, value(c)?]),
+            _ => ParentValues::Many(parents.iter().map(value).collect::<Result<_, _>>()?),
+        })
+    }
+
+    fn parent_value(&self, par: &CellId, fallback: Option<&T>) -> Result<T, CellError> {
+        match self.compute.get(par).and_then(|c| c.error.clone()) {
+            Some(e) => fallback.cloned().ok_or(e),
+            None => Ok(self.value(*par).unwrap()),
+        }
+    }
+
+    // Each cell's value, and whether it was in an error state.
+    fn compute_values(&self, queue: &[CellId]) -> Vec<(CellId, T, bool)> {
+        queue
+            .iter()
+            .map(|id| {
+                let cell = &self.compute[id];
+                (*id, cell.val.clone(), cell.error.is_some())
+            })
+            .collect()
+    }
+
+    // Callbacks only see the settled value, and only when it differs from the
+    // value the cell had before propagation started or the cell recovered
+    // from an error, even if it recovered to its last good value. The source
+    // reported to callbacks is the first input written since the last
+    // propagation that ended up changed. Returns how many compute cells
+    // changed and how many callbacks and observer calls were made.
+    fn fire_callbacks(&mut self, before: &[(CellId, T, bool)]) -> (usize, usize) {
+        let generation = self.generation + 1;
+        let mut fired = 0;
+        // Each write as made, possibly through an alias, and the cell it went to.
+        let written: Vec<(CellId, CellId, T)> = mem::take(&mut self.written_inputs)
+            .into_iter()
+            .map(|(id, old_val)| (id, self.backing(id), old_val))
+            .collect();
+        let source = written.iter().find_map(|(id, backing, old_val)| match id {
+            CellId::Input(input) if self.inputs[backing].val != *old_val => Some(*input),
+            _ => None,
+        });
+        // Inputs go first so observers see the source of a change before
+        // anything derived from it.
+        let mut inputs_changed = false;
+        for (_, input_id, old_val) in &written {
+            let cell = self.inputs.get_mut(input_id).unwrap();
+            if cell.val != *old_val {
+                inputs_changed = true;
+                if self.queue_notifications {
+                    self.notifications.push((*input_id, cell.val.clone()));
+                } else {
+                    fired += cell.callbacks.fire(*input_id, old_val, &cell.val, source);
+                }
+            }
+        }
+
+        let mut changed: Vec<CellId> = Vec::new();
+        for (cell_id, old_val, errored) in before {
+            let cell = self.compute.get_mut(cell_id).unwrap();
+            let differs = cell.val != *old_val;
+            if cell.error.is_none() && (*errored || differs) {
+                changed.push(*cell_id);
+                cell.changed_at = generation;
+                if let (Some(history), true) = (&mut cell.history, differs) {
+                    history.push(cell.val.clone());
+                }
+                if self.queue_notifications {
+                    self.notifications.push((*cell_id, cell.val.clone()));
+                } else {
+                    fired += cell.callbacks.fire(*cell_id, old_val, &cell.val, source);
+                }
+            }
+        }
+
+        if !self.queue_notifications {
+            for (_, observer) in self.observers.iter_mut() {
+                for cell_id in &changed {
+                    (observer.borrow_mut())(*cell_id, self.compute[cell_id].val.clone());
+                }
+                fired += changed.len();
+            }
+        }
+
+        if inputs_changed || !changed.is_empty() {
+            self.generation = generation;
+        }
+        if let Some(log) = &mut self.change_log {
+            let affected: Vec<(ComputeCellId, T)> = changed
+                .iter()
+                .filter_map(|id| match id {
+                    CellId::Compute(cell) => Some((*cell, self.compute[id].val.clone())),
+                    _ => None,
+                })
+                .collect();
+            for (id, backing, old) in written {
+                let new = &self.inputs[&backing].val;
+                if let CellId::Input(input) = id {
+                    if *new != old {
+                        let (new, affected) = (new.clone(), affected.clone());
+                        log.push(ChangeLogEntry { generation, input, old, new, affected });
+                    }
+                }
+            }
+        }
+        (changed.len(), fired)
+    }
+
+    // From now on every input write that changes a value is recorded, along
+    // with what it changed downstream. Writes before this aren't.
+    pub fn enable_change_log(&mut self) {
+        self.change_log.get_or_insert_with(Vec::new);
+    }
+
+    pub fn change_log(&self) -> &[ChangeLogEntry<T>] {
+        self.change_log.as_deref().unwrap_or(&[])
+    }
+
+    // Keeps logging, but forgets what was recorded so far.
+    pub fn clear_change_log(&mut self) {
+        if let Some(log) = &mut self.change_log {
+            log.clear();
+        }
+    }
+
+    // When enabled, propagation no longer runs callbacks or observers; each
+    // net change is queued for `drain_notifications` instead, inputs first and
+    // then compute cells in topological order. Turning it off keeps whatever
+    // is already queued.
+    pub fn set_queue_notifications(&mut self, enabled: bool) {
+        self.queue_notifications = enabled;
+    }
+
+    pub fn drain_notifications(&mut self) -> Vec<(CellId, T)> {
+        mem::take(&mut self.notifications)
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn last_changed_generation(&self, id: ComputeCellId) -> Option<u64> {
+        self.compute.get(&CellId::Compute(id)).map(|c| c.changed_at)
+    }
+    
+    pub fn add_callback<F: 'a + MaybeSend + FnMut(T)>(
+        &mut self,
+        _id: ComputeCellId,
+        _callback: F,
+    ) -> Option<CallbackId> {
+        self.try_add_callback(_id, _callback).ok()
+    }
+
+    // Stored like any other callback, so `remove_callback` takes the id this
+    // returns.
+    pub fn add_observer(
+        &mut self,
+        id: ComputeCellId,
+        mut observer: Box<dyn CellObserver<T> + 'a>,
+    ) -> Option<CallbackId>
+    where
+        T: 'a,
+    {
+        let on_change = move |id, value| observer.on_change(id, value);
+        self.insert_callback(
+            id,
+            Callback {
+                func: CallbackFn::WithCell(Shared::new(Lock::new(on_change))),
+                once: false,
+                priority: 0,
+                filter: None,
+                alive: None,
+            },
+        )
+        .ok()
+    }
+
+    pub fn try_add_callback<F: 'a + MaybeSend + FnMut(T)>(
+        &mut self,
+        id: ComputeCellId,
+        callback: F,
+    ) -> Result<CallbackId, AddCallbackError> {
+        self.insert_callback(
+            id,
+            Callback {
+                func: CallbackFn::Value(Shared::new(Lock::new(callback))),
+                once: false,
+                priority: 0,
+                filter: None,
+                alive: None,
+            },
+        )
+    }
+
+    // `filter` sees the old and new value of every change and decides whether
+    // `callback` runs for it. Whether the cell changed at all is still decided
+    // by `PartialEq`, so this can only make a callback quieter.
+    pub fn add_callback_filtered<G, F>(
+        &mut self,
+        id: ComputeCellId,
+        filter: G,
+        callback: F,
+    ) -> Option<CallbackId>
+    where
+        G: 'a + MaybeSend + MaybeSync + Fn(&T, &T) -> bool,
+        F: 'a + MaybeSend + FnMut(T),
+    {
+        self.insert_callback(
+            id,
+            Callback {
+                func: CallbackFn::Value(Shared::new(Lock::new(callback))),
+                once: false,
+                priority: 0,
+                filter: Some(Shared::new(filter)),
+                alive: None,
+            },
+        )
+        .ok()
+    }
+
+    pub fn add_input_callback<F: 'a + MaybeSend + FnMut(T)>(
+        &mut self,
+        id: InputCellId,
+        callback: F,
+    ) -> Option<CallbackId> {
+        let cell = self.inputs.get_mut(&self.backing(CellId::Input(id)))?;
+        if cell.callbacks.len() >= self.callback_limit {
+            return None;
+        }
+        let id = CallbackId(self.next_callback_id);
+        self.next_callback_id += 1;
+        cell.callbacks.insert(
+            id,
+            Callback {
+                func: CallbackFn::Value(Shared::new(Lock::new(callback))),
+                once: false,
+                priority: 0,
+                filter: None,
+                alive: None,
+            },
+        );
+        Some(id)
+    }
+
+    pub fn remove_input_callback(
+        &mut self,
+        cell: InputCellId,
+        callback: CallbackId,
+    ) -> Result<(), RemoveCallbackError> {
+        match self.inputs.get_mut(&self.backing(CellId::Input(cell))) {
+            None => Err(RemoveCallbackError::NonexistentCell),
+            Some(cell) => match cell.callbacks.remove(&callback) {
+                None => Err(RemoveCallbackError::NonexistentCallback),
+                Some(_) => Ok(()),
+            },
+        }
+    }
+
+    pub fn add_callback_with_priority<F: 'a + MaybeSend + FnMut(T)>(
+        &mut self,
+        id: ComputeCellId,
+        priority: i32,
+        callback: F,
+    ) -> Option<CallbackId> {
+        self.insert_callback(
+            id,
+            Callback {
+                func: CallbackFn::Value(Shared::new(Lock::new(callback))),
+                once: false,
+                priority,
+                filter: None,
+                alive: None,
+            },
+        )
+        .ok()
+    }
+
+    pub fn add_callback_with_prev<F: 'a + MaybeSend + FnMut(T, T)>(
+        &mut self,
+        id: ComputeCellId,
+        callback: F,
+    ) -> Option<CallbackId> {
+        self.insert_callback(
+            id,
+            Callback {
+                func: CallbackFn::WithPrev(Shared::new(Lock::new(callback))),
+                once: false,
+                priority: 0,
+                filter: None,
+                alive: None,
+            },
+        )
+        .ok()
+    }
+
+    // Also passes the input whose write set off the change; with several
+    // written at once, the first of them. Changes no input caused, such as
+    // those from `invalidate`, don't reach these callbacks.
+    pub fn add_callback_with_source<F: 'a + MaybeSend + FnMut(T, InputCellId)>(
+        &mut self,
+        id: ComputeCellId,
+        callback: F,
+    ) -> Option<CallbackId> {
+        self.insert_callback(
+            id,
+            Callback {
+                func: CallbackFn::WithSource(Shared::new(Lock::new(callback))),
+                once: false,
+                priority: 0,
+                filter: None,
+                alive: None,
+            },
+        )
+        .ok()
+    }
+
+    pub fn add_callback_once<F: 'a + MaybeSend + FnMut(T)>(
+        &mut self,
+        id: ComputeCellId,
+        callback: F,
+    ) -> Option<CallbackId> {
+        self.insert_callback(
+            id,
+            Callback {
+                func: CallbackFn::Value(Shared::new(Lock::new(callback))),
+                once: true,
+                priority: 0,
+                filter: None,
+                alive: None,
+            },
+        )
+        .ok()
+    }
+
+    // For callbacks feeding something that can go away, which are dropped as
+    // soon as `alive` says it's gone instead of lingering until removed.
+    #[cfg(feature = "async")]
+    pub(crate) fn add_callback_while<A, F>(
+        &mut self,
+        id: ComputeCellId,
+        once: bool,
+        alive: A,
+        callback: F,
+    ) -> Option<CallbackId>
+    where
+        A: 'a + MaybeSend + MaybeSync + Fn() -> bool,
+        F: 'a + MaybeSend + FnMut(T),
+    {
+        self.insert_callback(
+            id,
+            Callback {
+                func: CallbackFn::Value(Shared::new(Lock::new(callback))),
+                once,
+                priority: 0,
+                filter: None,
+                alive: Some(Shared::new(alive)),
+            },
+        )
+        .ok()
+    }
+
+    fn insert_callback(
+        &mut self,
+        id: ComputeCellId,
+        callback: Callback<'a, T>,
+    ) -> Result<CallbackId, AddCallbackError> {
+        let Some(cell) = self.compute.get_mut(&CellId::Compute(id)) else {
+            return Err(AddCallbackError::NonexistentCell);
+        };
+        // Callbacks that are already gone don't count against the limit.
+        cell.callbacks.prune();
+        if cell.callbacks.len() >= self.callback_limit {
+            return Err(AddCallbackError::LimitReached);
+        }
+        let id = CallbackId(self.next_callback_id);
+        self.next_callback_id += 1;
+        cell.callbacks.insert(id, callback);
+        Ok(id)
+    }
+    
     pub fn remove_callback(
         &mut self,
         cell: ComputeCellId,
@@ -192,17 +2375,694 @@ impl<'a, T: Copy + PartialEq> Reactor<'a, T> {
     ) -> Result<(), RemoveCallbackError> {
         match self.compute.get_mut(&CellId::Compute(cell)) {
             None => Err(RemoveCallbackError::NonexistentCell),
-            Some(cell) => {
-                match cell.callbacks.remove(&callback) {
-                    None => Err(RemoveCallbackError::NonexistentCallback),
-                    Some(_) => {
-                        cell.callbacks.remove(&callback);
-                        Ok(())
-                    }
+            Some(cell) => match cell.callbacks.remove(&callback) {
+                None => Err(RemoveCallbackError::NonexistentCallback),
+                Some(_) => Ok(()),
+            },
+        }
+    }
+
+    // Removes every callback on the cell, returning how many there were.
+    pub fn clear_callbacks(&mut self, id: ComputeCellId) -> Option<usize> {
+        let cell = self.compute.get_mut(&CellId::Compute(id))?;
+        let removed = cell.callbacks.len();
+        cell.callbacks = CallbackList::new();
+        Some(removed)
+    }
+
+    // In the order the callbacks were added.
+    pub fn callbacks(&self, id: ComputeCellId) -> Option<Vec<CallbackId>> {
+        self.compute.get(&CellId::Compute(id)).map(|c| c.callbacks.ids())
+    }
+
+    pub fn subscribe_all<F>(&mut self, callback: F) -> CallbackId
+    where
+        F: 'a + MaybeSend + FnMut(CellId, T),
+    {
+        let id = CallbackId(self.next_callback_id);
+        self.next_callback_id += 1;
+        self.observers.push((id, Box::new(Lock::new(callback))));
+        id
+    }
+
+    pub fn unsubscribe_all(&mut self, id: CallbackId) -> bool {
+        let len = self.observers.len();
+        self.observers.retain(|(oid, _)| *oid != id);
+        self.observers.len() != len
+    }
+
+    pub fn snapshot(&self) -> ReactorSnapshot<T> {
+        let inputs = self.inputs.iter().map(|(id, c)| (*id, c.val.clone()));
+        let compute = self.compute.iter().map(|(id, c)| (*id, c.val.clone()));
+        let errored = self.compute.iter().filter(|(_, c)| c.error.is_some()).map(|(id, _)| *id);
+        ReactorSnapshot { values: inputs.chain(compute).collect(), errored: errored.collect() }
+    }
+
+    pub fn restore(&mut self, snap: &ReactorSnapshot<T>) -> Result<(), RestoreError> {
+        if snap.values.len() != self.inputs.len() + self.compute.len()
+            || snap
+                .values
+                .keys()
+                .any(|id| !self.inputs.contains_key(id) && !self.compute.contains_key(id))
+        {
+            return Err(RestoreError::ShapeMismatch);
+        }
+
+        let mut topo = self.topological_order();
+        topo.retain(|id| matches!(id, CellId::Compute(_)));
+        let before = self.compute_values(&topo);
+        for (id, val) in &snap.values {
+            match id {
+                CellId::Input(_) => self.inputs.get_mut(id).unwrap().val = val.clone(),
+                CellId::Compute(_) => self.compute.get_mut(id).unwrap().val = val.clone(),
+                CellId::Constant(_) => {}
+            }
+        }
+        // Errors aren't stored, so cells errored either then or now are
+        // recomputed, which brings back the error state they had then.
+        for &id in &topo {
+            let cell = &self.compute[&id];
+            if cell.error.is_none() && !snap.errored.contains(&id) {
+                continue;
+            }
+            let result = self.parent_values(cell).and_then(|values| (cell.func)(&values));
+            let cell = self.compute.get_mut(&id).unwrap();
+            match result {
+                Ok(val) => {
+                    cell.val = val;
+                    cell.error = None;
                 }
+                Err(e) => cell.error = Some(e),
+            }
+        }
+        self.refresh_all_typed();
+        self.fire_callbacks(&before);
+        Ok(())
+    }
+
+    // Every cell whose value differs from `snap`, as `(id, then, now)` in
+    // topological order. Cells that were created or removed since the
+    // snapshot was taken are left out.
+    pub fn diff(&self, snap: &ReactorSnapshot<T>) -> Vec<(CellId, T, T)> {
+        self.topological_order()
+            .into_iter()
+            .filter_map(|id| {
+                let then = snap.values.get(&id)?;
+                let now = match id {
+                    CellId::Input(_) => &self.inputs[&id].val,
+                    CellId::Compute(_) => &self.compute[&id].val,
+                    CellId::Constant(_) => return None,
+                };
+                (then != now).then(|| (id, then.clone(), now.clone()))
+            })
+            .collect()
+    }
+
+    // Compute closures and callbacks are shared with the fork rather than
+    // copied, so a stateful callback sees the changes of both reactors.
+    // Subscribers, one-shot callbacks and change streams stay with the
+    // original reactor.
+    pub fn fork(&self) -> Reactor<'a, T> {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|(id, c)| {
+                let cell = InputCell {
+                    val: c.val.clone(),
+                    initial: c.initial.clone(),
+                    children: c.children.clone(),
+                    callbacks: c.callbacks.fork(),
+                    label: c.label.clone(),
+                    validator: c.validator.clone(),
+                };
+                (*id, Box::new(cell))
+            })
+            .collect();
+        let compute = self
+            .compute
+            .iter()
+            .map(|(id, c)| {
+                let cell = ComputeCell {
+                    val: c.val.clone(),
+                    children: c.children.clone(),
+                    parents: c.parents.clone(),
+                    callbacks: c.callbacks.fork(),
+                    func: Shared::clone(&c.func),
+                    preview: c.preview.clone(),
+                    error: c.error.clone(),
+                    changed_at: c.changed_at,
+                    frozen: c.frozen,
+                    history: c.history.clone(),
+                    label: c.label.clone(),
+                    fallback: c.fallback.clone(),
+                    recomputes: c.recomputes,
+                };
+                (*id, Box::new(cell))
+            })
+            .collect();
+        let mut forked = Reactor {
+            inputs,
+            compute,
+            constants: self.constants.clone(),
+            observers: Vec::new(),
+            next_callback_id: self.next_callback_id,
+            next_input_id: self.next_input_id,
+            next_compute_id: self.next_compute_id,
+            transaction: self.transaction.clone(),
+            generation: self.generation,
+            written_inputs: self.written_inputs.clone(),
+            max_propagation_steps: self.max_propagation_steps,
+            callback_limit: self.callback_limit,
+            queue_notifications: self.queue_notifications,
+            notifications: self.notifications.clone(),
+            metrics: self.metrics.clone(),
+            propagating: self.propagating,
+            change_log: self.change_log.clone(),
+            frame: self.frame.clone(),
+            typed: self.typed.iter().map(|(id, c)| (*id, c.fork())).collect(),
+            next_typed_id: self.next_typed_id,
+            #[cfg(debug_assertions)]
+            recomputed: self.recomputed.clone(),
+            aliases: self.aliases.clone(),
+        };
+        forked.refresh_all_typed();
+        forked
+    }
+
+    pub fn to_dot(&self) -> String
+    where
+        T: Display,
+    {
+        let order = self.topological_order();
+        let mut out = String::from("digraph reactor {\n");
+        for &id in &order {
+            let shape = match id {
+                CellId::Input(_) => "box",
+                CellId::Compute(_) => "ellipse",
+                CellId::Constant(_) => "diamond",
+            };
+            let label = match self.value_ref(id) {
+                Some(val) => format!("{} = {}", self.display_name(id), val),
+                None => format!("{} = <error>", self.display_name(id)),
+            };
+            out.push_str(&format!(
+                "    {} [shape={}, label=\"{}\"];\n",
+                node_name(id),
+                shape,
+                label.replace('\\', "\\\\").replace('"', "\\\"")
+            ));
+        }
+        for &id in &order {
+            for &child in self.children_of(id).unwrap() {
+                out.push_str(&format!("    {} -> {};\n", node_name(id), node_name(child)));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    // Labels are only used by `to_dot` and `debug_dump`; they have no effect
+    // on computation.
+    pub fn set_label(&mut self, id: CellId, label: impl Into<String>) -> bool {
+        let id = self.backing(id);
+        let slot = match id {
+            CellId::Input(_) => self.inputs.get_mut(&id).map(|c| &mut c.label),
+            CellId::Compute(_) => self.compute.get_mut(&id).map(|c| &mut c.label),
+            CellId::Constant(_) => self.constants.get_mut(&id).map(|c| &mut c.label),
+        };
+        match slot {
+            Some(slot) => {
+                *slot = Some(label.into());
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn label(&self, id: CellId) -> Option<&str> {
+        let id = self.backing(id);
+        let label = match id {
+            CellId::Input(_) => &self.inputs.get(&id)?.label,
+            CellId::Compute(_) => &self.compute.get(&id)?.label,
+            CellId::Constant(_) => &self.constants.get(&id)?.label,
+        };
+        label.as_deref()
+    }
+
+    // The label if there is one, otherwise a name made from the id.
+    fn display_name(&self, id: CellId) -> String {
+        match self.label(id) {
+            Some(label) => label.to_string(),
+            None => node_name(id),
+        }
+    }
+
+    // One line per cell in topological order, for logs and test failures.
+    pub fn debug_dump(&self) -> String
+    where
+        T: Debug,
+    {
+        let names = |ids: &[CellId]| {
+            ids.iter().map(|&id| self.display_name(id)).collect::<Vec<_>>().join(", ")
+        };
+        let mut out = String::new();
+        for id in self.topological_order() {
+            let info = self.cell_info(id).unwrap();
+            let kind = match id {
+                CellId::Input(_) => "input",
+                CellId::Compute(_) => "compute",
+                CellId::Constant(_) => "constant",
+            };
+            let value = match info.value() {
+                Some(val) => format!("{val:?}"),
+                None => "<error>".to_string(),
+            };
+            out.push_str(&format!(
+                "{} {} value={} parents=[{}] children=[{}] callbacks={}\n",
+                self.display_name(id),
+                kind,
+                value,
+                names(info.parents()),
+                names(info.children()),
+                info.callback_count()
+            ));
+        }
+        out
+    }
+
+    // The new dependency's value is passed to the closure after the existing
+    // ones, and the cell is recomputed straight away. If it can't be, the
+    // dependency isn't added either.
+    pub fn add_dependency(
+        &mut self,
+        cell: ComputeCellId,
+        dependency: CellId,
+    ) -> Result<(), CreateCellError> {
+        let cell = CellId::Compute(cell);
+        let dependency = self.backing(dependency);
+        if !self.compute.contains_key(&cell) {
+            return Err(CreateCellError::NonexistentCell);
+        }
+        if self.children_of(dependency).is_none() {
+            return Err(CreateCellError::NonexistentDependency(dependency));
+        }
+        if self.parents_of(cell).contains(&dependency) {
+            return Err(CreateCellError::DuplicateDependency(dependency));
+        }
+        if let Some(path) = self.find_cycle(cell, &[dependency]) {
+            return Err(CreateCellError::Cycle(path));
+        }
+
+        self.compute.get_mut(&cell).unwrap().parents.push(dependency);
+        self.children_of_mut(dependency).unwrap().push(cell);
+        if let Err(e) = self.propagate(&[cell]) {
+            self.compute.get_mut(&cell).unwrap().parents.pop();
+            self.children_of_mut(dependency).unwrap().pop();
+            return Err(CreateCellError::Propagation(e));
+        }
+        Ok(())
+    }
+
+    // Returns `false` if `dependency` isn't one of the cell's dependencies, or
+    // is the only one left: like at creation, a cell needs at least one. Also
+    // `false`, with the dependency kept, if the cell can't be recomputed.
+    pub fn remove_dependency(&mut self, cell: ComputeCellId, dependency: CellId) -> bool {
+        let cell = CellId::Compute(cell);
+        let dependency = self.backing(dependency);
+        let Some(compute) = self.compute.get_mut(&cell) else {
+            return false;
+        };
+        let Some(pos) = compute.parents.iter().position(|&p| p == dependency) else {
+            return false;
+        };
+        if compute.parents.len() == 1 {
+            return false;
+        }
+
+        compute.parents.remove(pos);
+        let children = self.children_of_mut(dependency).unwrap();
+        let child_pos = children.iter().position(|&c| c == cell);
+        if let Some(child_pos) = child_pos {
+            children.remove(child_pos);
+        }
+        if self.propagate(&[cell]).is_err() {
+            self.compute.get_mut(&cell).unwrap().parents.insert(pos, dependency);
+            if let Some(child_pos) = child_pos {
+                self.children_of_mut(dependency).unwrap().insert(child_pos, cell);
+            }
+            return false;
+        }
+        true
+    }
+
+    // Swaps `old` for `new` in the cell's dependencies, keeping its position
+    // among them, and recomputes the cell. Fails with
+    // `NonexistentDependency(old)` when `old` isn't a dependency of the cell,
+    // and leaves the cell as it was if it can't be recomputed.
+    pub fn repoint_dependency(
+        &mut self,
+        cell: ComputeCellId,
+        old: CellId,
+        new: CellId,
+    ) -> Result<(), CreateCellError> {
+        let cell = CellId::Compute(cell);
+        let (old, new) = (self.backing(old), self.backing(new));
+        let compute = self.compute.get(&cell).ok_or(CreateCellError::NonexistentCell)?;
+        let pos = compute
+            .parents
+            .iter()
+            .position(|&p| p == old)
+            .ok_or(CreateCellError::NonexistentDependency(old))?;
+        if self.children_of(new).is_none() {
+            return Err(CreateCellError::NonexistentDependency(new));
+        }
+        if old == new {
+            return Ok(());
+        }
+        if self.parents_of(cell).contains(&new) {
+            return Err(CreateCellError::DuplicateDependency(new));
+        }
+        if let Some(path) = self.find_cycle(cell, &[new]) {
+            return Err(CreateCellError::Cycle(path));
+        }
+
+        self.compute.get_mut(&cell).unwrap().parents[pos] = new;
+        let children = self.children_of_mut(old).unwrap();
+        let child_pos = children.iter().position(|&c| c == cell);
+        if let Some(child_pos) = child_pos {
+            children.remove(child_pos);
+        }
+        self.children_of_mut(new).unwrap().push(cell);
+        if let Err(e) = self.propagate(&[cell]) {
+            self.compute.get_mut(&cell).unwrap().parents[pos] = old;
+            self.children_of_mut(new).unwrap().pop();
+            if let Some(child_pos) = child_pos {
+                self.children_of_mut(old).unwrap().insert(child_pos, cell);
+            }
+            return Err(CreateCellError::Propagation(e));
+        }
+        Ok(())
+    }
+
+    pub fn remove_cell(&mut self, id: ComputeCellId) -> Result<(), RemoveCellError> {
+        let id = CellId::Compute(id);
+        match self.compute.get(&id) {
+            None => return Err(RemoveCellError::NonexistentCell),
+            Some(cell) if self.has_dependents(id, cell) => {
+                return Err(RemoveCellError::HasDependents)
+            }
+            Some(_) => {}
+        }
+
+        let cell = self.compute.remove(&id).unwrap();
+        for parent in cell.parents {
+            self.children_of_mut(parent).unwrap().retain(|&c| c != id);
+        }
+        // A pending recompute of the cell would otherwise outlive it.
+        if let Some(tx) = &mut self.transaction {
+            tx.dirty.retain(|&d| d != id);
+        }
+        Ok(())
+    }
+
+    // From now on, every value the cell settles on is recorded, keeping the
+    // last `capacity`. Like callbacks, only net changes are recorded, and
+    // enabling it again starts over with the new capacity.
+    pub fn enable_history(&mut self, id: ComputeCellId, capacity: usize) -> bool {
+        match self.compute.get_mut(&CellId::Compute(id)) {
+            Some(cell) => {
+                let values = VecDeque::with_capacity(capacity);
+                cell.history = Some(History { capacity, values });
+                true
             }
+            None => false,
+        }
+    }
+
+    // `None` if the cell doesn't exist or history was never enabled for it.
+    pub fn history(&self, id: ComputeCellId) -> Option<&[T]> {
+        let history = self.compute.get(&CellId::Compute(id))?.history.as_ref()?;
+        Some(history.values.as_slices().0)
+    }
+
+    // Counts every run of the cell's closure during propagation, including
+    // those that left its value unchanged. Creating the cell doesn't count.
+    pub fn recompute_count(&self, id: ComputeCellId) -> Option<u64> {
+        self.compute.get(&CellId::Compute(id)).map(|c| c.recomputes)
+    }
+
+    // Panics if the latest propagation ran any cell's closure more than once,
+    // which would mean a diamond was recomputed redundantly. Only checks in
+    // debug builds, where the counts are kept.
+    #[track_caller]
+    pub fn assert_single_recompute(&self) {
+        #[cfg(debug_assertions)]
+        {
+            let mut redundant: Vec<(CellId, usize)> =
+                self.recomputed.iter().filter(|(_, &n)| n > 1).map(|(id, n)| (*id, *n)).collect();
+            redundant.sort_by_key(|(id, _)| cell_index(*id));
+            if let Some((id, n)) = redundant.first() {
+                panic!("cell {} was recomputed {n} times in one propagation", node_name(*id));
+            }
+        }
+    }
+
+    // While frozen, a cell ignores upstream changes (so its callbacks stay
+    // quiet) and its dependents keep seeing the value it had when frozen.
+    pub fn freeze(&mut self, id: ComputeCellId) -> bool {
+        match self.compute.get_mut(&CellId::Compute(id)) {
+            Some(cell) => {
+                cell.frozen = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Catches the cell up with its dependencies, propagating if that changes
+    // its value. The cell stays frozen, and this returns `false`, if it can't
+    // be recomputed.
+    pub fn unfreeze(&mut self, id: ComputeCellId) -> bool {
+        let id = CellId::Compute(id);
+        let Some(cell) = self.compute.get_mut(&id) else {
+            return false;
+        };
+        if cell.frozen {
+            cell.frozen = false;
+            if self.propagate(&[id]).is_err() {
+                self.compute.get_mut(&id).unwrap().frozen = true;
+                return false;
+            }
+        }
+        true
+    }
+
+    // Renumbers the cells of each kind densely from zero, keeping their
+    // relative order, and returns where every old id went. Ids held from
+    // before are stale afterwards and have to be translated through the map:
+    // unlike after `remove_cell`, they may now name a different cell.
+    pub fn compact(&mut self) -> HashMap<CellId, CellId> {
+        let mut mapping: HashMap<CellId, CellId> = HashMap::new();
+        let mut ids: Vec<CellId> = self.inputs.keys().copied().collect();
+        ids.sort_by_key(|&id| cell_index(id));
+        for (i, id) in ids.into_iter().enumerate() {
+            mapping.insert(id, CellId::Input(InputCellId(i)));
+        }
+        let mut ids: Vec<CellId> = self.compute.keys().copied().collect();
+        ids.sort_by_key(|&id| cell_index(id));
+        for (i, id) in ids.into_iter().enumerate() {
+            mapping.insert(id, CellId::Compute(ComputeCellId(i)));
+        }
+        let mut ids: Vec<CellId> = self.constants.keys().copied().collect();
+        ids.sort_by_key(|&id| cell_index(id));
+        for (i, id) in ids.into_iter().enumerate() {
+            mapping.insert(id, CellId::Constant(ConstantCellId(i)));
+        }
+        // Aliases are numbered after the inputs they share an id space with.
+        let mut ids: Vec<InputCellId> = self.aliases.keys().copied().collect();
+        ids.sort_by_key(|id| id.0);
+        for (i, id) in ids.into_iter().enumerate() {
+            mapping.insert(CellId::Input(id), CellId::Input(InputCellId(self.inputs.len() + i)));
+        }
+        let input = |id: &InputCellId| match mapping[&CellId::Input(*id)] {
+            CellId::Input(id) => id,
+            _ => unreachable!(),
+        };
+        self.aliases = self.aliases.iter().map(|(alias, id)| (input(alias), input(id))).collect();
+
+        let remap = |ids: &mut Vec<CellId>| ids.iter_mut().for_each(|id| *id = mapping[id]);
+        self.inputs = mem::take(&mut self.inputs)
+            .into_iter()
+            .map(|(id, mut cell)| {
+                remap(&mut cell.children);
+                (mapping[&id], cell)
+            })
+            .collect();
+        self.compute = mem::take(&mut self.compute)
+            .into_iter()
+            .map(|(id, mut cell)| {
+                remap(&mut cell.children);
+                remap(&mut cell.parents);
+                (mapping[&id], cell)
+            })
+            .collect();
+        self.constants = mem::take(&mut self.constants)
+            .into_iter()
+            .map(|(id, mut cell)| {
+                remap(&mut cell.children);
+                (mapping[&id], cell)
+            })
+            .collect();
+        self.typed.values_mut().for_each(|cell| remap(&mut cell.parents));
+        // Only ever describes the latest propagation, which the new ids
+        // no longer line up with.
+        #[cfg(debug_assertions)]
+        self.recomputed.clear();
+        if let Some(tx) = &mut self.transaction {
+            tx.original = mem::take(&mut tx.original)
+                .into_iter()
+                .map(|(id, val)| (mapping[&id], val))
+                .collect();
+            remap_existing(&mut tx.dirty, &mapping, |id| id);
+        }
+        // Queued notifications may still name cells removed since.
+        remap_existing(&mut self.notifications, &mapping, |(id, _)| id);
+        let pending = self.written_inputs.iter_mut().chain(self.frame.iter_mut().flatten());
+        for (id, _) in pending {
+            *id = mapping[id];
+        }
+        // Entries about cells removed since are dropped along with them.
+        if let Some(log) = &mut self.change_log {
+            log.retain_mut(|entry| match mapping.get(&CellId::Input(entry.input)) {
+                Some(&CellId::Input(input)) => {
+                    entry.input = input;
+                    entry.affected.retain_mut(|(cell, _)| {
+                        match mapping.get(&CellId::Compute(*cell)) {
+                            Some(&CellId::Compute(id)) => {
+                                *cell = id;
+                                true
+                            }
+                            _ => false,
+                        }
+                    });
+                    true
+                }
+                _ => false,
+            });
+        }
+        self.next_input_id = self.inputs.len() + self.aliases.len();
+        self.next_compute_id = self.compute.len();
+        mapping
+    }
+}
+
+// The graph structure alone, which needs nothing from `T`.
+impl<'a, T> Reactor<'a, T> {
+    // Every cell with its children, inputs first, then constants, then
+    // compute cells, each group ordered by id.
+    pub fn adjacency(&self) -> Vec<(CellId, Vec<CellId>)> {
+        let inputs = self.inputs.iter().map(|(id, c)| (*id, c.children.clone()));
+        let constants = self.constants.iter().map(|(id, c)| (*id, c.children.clone()));
+        let compute = self.compute.iter().map(|(id, c)| (*id, c.children.clone()));
+        sorted_by_id(inputs.chain(constants).chain(compute).collect())
+    }
+
+    // Like `adjacency`, but listing each cell's parents instead.
+    pub fn reverse_adjacency(&self) -> Vec<(CellId, Vec<CellId>)> {
+        let inputs = self.inputs.keys().map(|id| (*id, Vec::new()));
+        let constants = self.constants.keys().map(|id| (*id, Vec::new()));
+        let compute = self.compute.iter().map(|(id, c)| (*id, c.parents.clone()));
+        sorted_by_id(inputs.chain(constants).chain(compute).collect())
+    }
+}
+
+fn sorted_by_id(mut cells: Vec<(CellId, Vec<CellId>)>) -> Vec<(CellId, Vec<CellId>)> {
+    cells.sort_by_key(|(id, _)| {
+        let group = match id {
+            CellId::Input(_) => 0,
+            CellId::Constant(_) => 1,
+            CellId::Compute(_) => 2,
+        };
+        (group, cell_index(*id))
+    });
+    cells
+}
+
+impl<'a, T: Clone + PartialEq> Default for Reactor<'a, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// One input per item. The ids aren't returned, so this suits fixtures that
+// find their cells again through `iter_cells`; `create_inputs_from` hands
+// them out.
+impl<'a, T: Clone + PartialEq> FromIterator<T> for Reactor<'a, T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut reactor = Self::new();
+        reactor.extend(iter);
+        reactor
+    }
+}
+
+impl<'a, T: Clone + PartialEq> Extend<T> for Reactor<'a, T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for val in iter {
+            self.create_input(val);
+        }
+    }
+}
+
+// Renumbers the cell each entry names after a `compact`, dropping entries
+// about cells that no longer exist.
+fn remap_existing<E>(
+    entries: &mut Vec<E>,
+    mapping: &HashMap<CellId, CellId>,
+    id: impl Fn(&mut E) -> &mut CellId,
+) {
+    entries.retain_mut(|entry| match mapping.get(id(entry)) {
+        Some(&new) => {
+            *id(entry) = new;
+            true
+        }
+        None => false,
+    });
+}
+
+// Records a write made while a frame is open, returning whether it differs
+// from the value the input was going to end the frame with.
+fn frame_write<T: PartialEq>(
+    frame: &mut Vec<(CellId, T)>,
+    id: CellId,
+    current: &T,
+    val: T,
+) -> bool {
+    match frame.iter_mut().find(|(i, _)| *i == id) {
+        Some((_, pending)) => {
+            let changed = *pending != val;
+            *pending = val;
+            changed
+        }
+        None if *current == val => false,
+        None => {
+            frame.push((id, val));
+            true
         }
     }
 }
 
+fn cell_index(id: CellId) -> usize {
+    match id {
+        CellId::Input(InputCellId(i))
+        | CellId::Compute(ComputeCellId(i))
+        | CellId::Constant(ConstantCellId(i)) => i,
+    }
+}
 
+fn node_name(id: CellId) -> String {
+    match id {
+        CellId::Input(InputCellId(i)) => format!("input{i}"),
+        CellId::Compute(ComputeCellId(i)) => format!("compute{i}"),
+        CellId::Constant(ConstantCellId(i)) => format!("constant{i}"),
+    }
+}
@@ -0,0 +1,63 @@
+// With the `thread-safe` feature every closure the reactor stores has to be
+// `Send`, and compute closures, which forks share, `Sync` as well; closures
+// that run with `&mut self` sit behind a `Lock` instead. That makes
+// `Reactor<T>` `Send` and `Sync` whenever `T` is, at the cost of rejecting
+// closures that capture `Rc`s, `RefCell`s and the like. Without the feature
+// these traits are implemented for every type and the bounds using them are
+// no-ops.
+
+#[cfg(feature = "thread-safe")]
+pub trait MaybeSend: Send {}
+#[cfg(feature = "thread-safe")]
+impl<T: Send + ?Sized> MaybeSend for T {}
+
+#[cfg(not(feature = "thread-safe"))]
+pub trait MaybeSend {}
+#[cfg(not(feature = "thread-safe"))]
+impl<T: ?Sized> MaybeSend for T {}
+
+#[cfg(feature = "thread-safe")]
+pub trait MaybeSync: Sync {}
+#[cfg(feature = "thread-safe")]
+impl<T: Sync + ?Sized> MaybeSync for T {}
+
+#[cfg(not(feature = "thread-safe"))]
+pub trait MaybeSync {}
+#[cfg(not(feature = "thread-safe"))]
+impl<T: ?Sized> MaybeSync for T {}
+
+// Interior mutability for state captured by closures, and for callbacks
+// shared between forks: a `RefCell`, or a `Mutex` when it has to be `Sync`.
+#[cfg(not(feature = "thread-safe"))]
+pub(crate) struct Lock<T: ?Sized>(core::cell::RefCell<T>);
+
+#[cfg(not(feature = "thread-safe"))]
+impl<T> Lock<T> {
+    pub(crate) fn new(val: T) -> Self {
+        Self(core::cell::RefCell::new(val))
+    }
+}
+
+#[cfg(not(feature = "thread-safe"))]
+impl<T: ?Sized> Lock<T> {
+    pub(crate) fn borrow_mut(&self) -> core::cell::RefMut<'_, T> {
+        self.0.borrow_mut()
+    }
+}
+
+#[cfg(feature = "thread-safe")]
+pub(crate) struct Lock<T: ?Sized>(std::sync::Mutex<T>);
+
+#[cfg(feature = "thread-safe")]
+impl<T> Lock<T> {
+    pub(crate) fn new(val: T) -> Self {
+        Self(std::sync::Mutex::new(val))
+    }
+}
+
+#[cfg(feature = "thread-safe")]
+impl<T: ?Sized> Lock<T> {
+    pub(crate) fn borrow_mut(&self) -> std::sync::MutexGuard<'_, T> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,137 @@
+#[cfg(not(feature = "thread-safe"))]
+use alloc::rc::Rc as Shared;
+#[cfg(feature = "thread-safe")]
+use alloc::sync::Arc as Shared;
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, vec::Vec};
+use core::any::Any;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+
+use crate::{CellId, CreateCellError, HashSet, MaybeSend, MaybeSync, Reactor};
+
+#[cfg(not(feature = "thread-safe"))]
+type TypedValue = Box<dyn Any>;
+#[cfg(feature = "thread-safe")]
+type TypedValue = Box<dyn Any + Send + Sync>;
+
+#[cfg(not(feature = "thread-safe"))]
+type TypedFn<'a, T> = Shared<dyn 'a + Fn(&[T]) -> TypedValue>;
+#[cfg(feature = "thread-safe")]
+type TypedFn<'a, T> = Shared<dyn 'a + Fn(&[T]) -> TypedValue + Send + Sync>;
+
+/// Names a cell created by `Reactor::create_compute_into`, whose value is a
+/// `U` rather than the reactor's `T`.
+pub struct TypedComputeCellId<U> {
+    index: usize,
+    marker: PhantomData<fn() -> U>,
+}
+
+// Implemented by hand so they don't require the same of `U`.
+impl<U> Clone for TypedComputeCellId<U> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<U> Copy for TypedComputeCellId<U> {}
+
+impl<U> PartialEq for TypedComputeCellId<U> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<U> Eq for TypedComputeCellId<U> {}
+
+impl<U> Hash for TypedComputeCellId<U> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+
+impl<U> fmt::Debug for TypedComputeCellId<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TypedComputeCellId").field(&self.index).finish()
+    }
+}
+
+impl<U> TypedComputeCellId<U> {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+// Typed cells sit at the edge of the graph: they read `T` cells, but since
+// their value isn't a `T` nothing can depend on them. That keeps them out of
+// `children` and the ordinary propagation, which only has to tell them when
+// one of their dependencies may have changed.
+pub(crate) struct TypedCell<'a, T> {
+    pub(crate) parents: Vec<CellId>,
+    func: TypedFn<'a, T>,
+    // `None` while a dependency is in an error state.
+    val: Option<TypedValue>,
+}
+
+impl<'a, T> TypedCell<'a, T> {
+    // Values can't be cloned through `Any`, so forks recompute them.
+    pub(crate) fn fork(&self) -> Self {
+        Self { parents: self.parents.clone(), func: Shared::clone(&self.func), val: None }
+    }
+}
+
+impl<'a, T: Clone + PartialEq> Reactor<'a, T> {
+    // Lets a cell's value have a different type than its dependencies, say a
+    // `bool` for whether some number crossed a threshold. Such a cell can't
+    // itself be used as a dependency, and while it exists its dependencies
+    // can't be removed.
+    pub fn create_compute_into<U, F>(
+        &mut self,
+        dependencies: &[CellId],
+        compute_func: F,
+    ) -> Result<TypedComputeCellId<U>, CreateCellError>
+    where
+        U: MaybeSend + MaybeSync + 'static,
+        F: Fn(&[T]) -> U + MaybeSend + MaybeSync + 'a,
+    {
+        let (_, values) = self.prepare_compute(dependencies, None)?;
+        let func: TypedFn<'a, T> =
+            Shared::new(move |v: &[T]| Box::new(compute_func(v)) as TypedValue);
+        let val = Some(func(&values));
+        let index = self.next_typed_id;
+        self.next_typed_id += 1;
+        let parents = dependencies.iter().map(|&d| self.backing(d)).collect();
+        self.typed.insert(index, TypedCell { parents, func, val });
+        Ok(TypedComputeCellId { index, marker: PhantomData })
+    }
+
+    // `None` for unknown ids and while a dependency is in an error state.
+    pub fn value_typed<U: Clone + 'static>(&self, id: TypedComputeCellId<U>) -> Option<U> {
+        let val = self.typed.get(&id.index)?.val.as_ref()?;
+        val.downcast_ref::<U>().cloned()
+    }
+
+    // For when values changed without a propagation to say which.
+    pub(crate) fn refresh_all_typed(&mut self) {
+        let parents = self.typed.values().flat_map(|c| c.parents.iter().copied()).collect();
+        self.refresh_typed(&parents);
+    }
+
+    // Recomputes the typed cells reading any of `touched`.
+    pub(crate) fn refresh_typed(&mut self, touched: &HashSet<CellId>) {
+        let stale: Vec<usize> = self
+            .typed
+            .iter()
+            .filter(|(_, cell)| cell.parents.iter().any(|p| touched.contains(p)))
+            .map(|(index, _)| *index)
+            .collect();
+        for index in stale {
+            let cell = &self.typed[&index];
+            let values: Result<Vec<T>, _> =
+                cell.parents.iter().map(|p| self.parent_value(p, None)).collect();
+            let val = values.ok().map(|values| (cell.func)(&values));
+            self.typed.get_mut(&index).unwrap().val = val;
+        }
+    }
+}
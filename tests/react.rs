@@ -52,7 +52,7 @@ fn error_creating_compute_cell_if_input_doesnt_exist() {
     assert_eq!(
         // Reactor::new() -> Creating a new reactor.
         Reactor::new().create_compute(&[CellId::Input(input)], |_| 0),
-        Err(CellId::Input(input))
+        Err(CreateCellError::NonexistentDependency(CellId::Input(input)))
     );
 }
 
@@ -65,7 +65,7 @@ fn do_not_break_cell_if_creating_compute_cell_with_valid_and_invalid_input() {
     let input = reactor.create_input(1);
     assert_eq!(
         reactor.create_compute(&[CellId::Input(input), CellId::Input(dummy_cell)], |_| 0),
-        Err(CellId::Input(dummy_cell))
+        Err(CreateCellError::NonexistentDependency(CellId::Input(dummy_cell)))
     );
     assert!(reactor.set_value(input, 5));
     assert_eq!(reactor.value(CellId::Input(input)), Some(5));
@@ -110,35 +110,27 @@ fn compute_cells_can_depend_on_other_compute_cells() {
 /// so it's not necessary to fully understand the implementation,
 /// though you are welcome to.
 struct CallbackRecorder {
-    // Note that this `Cell` is https://doc.rust-lang.org/std/cell/
-    // a mechanism to allow internal mutability,
-    // distinct from the cells (input cells, compute cells) in the reactor
-    value: std::cell::Cell<Option<i32>>,
+    // A `Mutex` rather than a `Cell`, so callbacks borrowing the recorder
+    // are `Send` and the same tests run with the `thread-safe` feature.
+    value: std::sync::Mutex<Option<i32>>,
 }
 
 impl CallbackRecorder {
     fn new() -> Self {
         CallbackRecorder {
-            value: std::cell::Cell::new(None),
+            value: std::sync::Mutex::new(None),
         }
     }
 
     fn expect_to_have_been_called_with(&self, v: i32) {
-        assert_ne!(
-            self.value.get(),
-            None,
-            "Callback was not called, but should have been"
-        );
-        assert_eq!(
-            self.value.replace(None),
-            Some(v),
-            "Callback was called with incorrect value"
-        );
+        let value = self.value.lock().unwrap().take();
+        assert_ne!(value, None, "Callback was not called, but should have been");
+        assert_eq!(value, Some(v), "Callback was called with incorrect value");
     }
 
     fn expect_not_to_have_been_called(&self) {
         assert_eq!(
-            self.value.get(),
+            *self.value.lock().unwrap(),
             None,
             "Callback was called, but should not have been"
         );
@@ -146,13 +138,31 @@ impl CallbackRecorder {
 
     fn callback_called(&self, v: i32) {
         assert_eq!(
-            self.value.replace(Some(v)),
+            self.value.lock().unwrap().replace(v),
             None,
             "Callback was called too many times; can't be called with {v}"
         );
     }
 }
 
+/// A `Cell<i32>` for closures the reactor stores, which have to be `Send`
+/// and `Sync` with the `thread-safe` feature.
+struct SyncCell(std::sync::atomic::AtomicI32);
+
+impl SyncCell {
+    fn new(v: i32) -> Self {
+        SyncCell(std::sync::atomic::AtomicI32::new(v))
+    }
+
+    fn get(&self) -> i32 {
+        self.0.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    fn set(&self, v: i32) {
+        self.0.store(v, std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
 #[test]
 fn compute_cells_fire_callbacks() {
     let cb = CallbackRecorder::new();
@@ -440,3 +450,2555 @@ fn adder_with_boolean_values() {
         );
     }
 }
+
+#[test]
+fn callbacks_fire_once_with_settled_value_in_a_diamond() {
+    let calls = std::sync::Mutex::new(Vec::new());
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let left = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let right = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] * 2)
+        .unwrap();
+    let output = reactor
+        .create_compute(&[CellId::Compute(left), CellId::Compute(right)], |v| {
+            v[0] + v[1]
+        })
+        .unwrap();
+    assert!(reactor
+        .add_callback(output, |v| calls.lock().unwrap().push(v))
+        .is_some());
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(*calls.lock().unwrap(), vec![16]);
+}
+
+#[test]
+fn batch_updates_fire_callbacks_once() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_input(2);
+    let sum = reactor
+        .create_compute(&[CellId::Input(a), CellId::Input(b)], |v| v[0] + v[1])
+        .unwrap();
+    assert!(reactor
+        .add_callback(sum, |v| cb.callback_called(v))
+        .is_some());
+    assert!(reactor.set_value_batch(&[(a, 10), (b, 20)]));
+    cb.expect_to_have_been_called_with(30);
+    assert_eq!(reactor.value(CellId::Input(a)), Some(10));
+    assert_eq!(reactor.value(CellId::Input(b)), Some(20));
+}
+
+#[test]
+fn batch_with_unknown_input_applies_nothing() {
+    let mut dummy_reactor = Reactor::new();
+    let _ = dummy_reactor.create_input(1);
+    let unknown = dummy_reactor.create_input(2);
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    assert!(!reactor.set_value_batch(&[(input, 5), (unknown, 6)]));
+    assert_eq!(reactor.value(CellId::Input(input)), Some(1));
+}
+
+#[test]
+fn deep_chains_do_not_overflow_the_stack() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(0);
+    let mut last = CellId::Input(input);
+    for _ in 0..50_000 {
+        last = CellId::Compute(reactor.create_compute(&[last], |v| v[0] + 1).unwrap());
+    }
+    assert!(reactor.set_value(input, 1));
+    assert_eq!(reactor.value(last), Some(50_001));
+}
+
+#[test]
+fn compute_cells_can_be_removed() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    assert!(reactor
+        .add_callback(output, |v| cb.callback_called(v))
+        .is_some());
+    assert_eq!(reactor.remove_cell(output), Ok(()));
+    assert_eq!(reactor.value(CellId::Compute(output)), None);
+    assert_eq!(
+        reactor.remove_cell(output),
+        Err(RemoveCellError::NonexistentCell)
+    );
+    assert!(reactor.set_value(input, 2));
+    cb.expect_not_to_have_been_called();
+}
+
+#[test]
+fn error_removing_a_cell_with_dependents() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let middle = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let output = reactor
+        .create_compute(&[CellId::Compute(middle)], |v| v[0] * 2)
+        .unwrap();
+    assert_eq!(
+        reactor.remove_cell(middle),
+        Err(RemoveCellError::HasDependents)
+    );
+    assert_eq!(reactor.remove_cell(output), Ok(()));
+    assert_eq!(reactor.remove_cell(middle), Ok(()));
+    assert!(reactor.set_value(input, 2));
+}
+
+#[test]
+fn dependencies_and_dependents_describe_the_wiring() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    assert_eq!(reactor.dependencies(CellId::Input(input)), Some(vec![]));
+    assert_eq!(
+        reactor.dependents(CellId::Input(input)),
+        Some(vec![CellId::Compute(output)])
+    );
+    assert_eq!(
+        reactor.dependencies(CellId::Compute(output)),
+        Some(vec![CellId::Input(input)])
+    );
+    assert_eq!(reactor.dependents(CellId::Compute(output)), Some(vec![]));
+    assert_eq!(Reactor::<i32>::new().dependencies(CellId::Input(input)), None);
+    assert_eq!(Reactor::<i32>::new().dependents(CellId::Compute(output)), None);
+}
+
+#[test]
+fn reactors_can_hold_non_copy_values() {
+    let mut reactor = Reactor::new();
+    let first = reactor.create_input(String::from("hello"));
+    let second = reactor.create_input(String::from("world"));
+    let greeting = reactor
+        .create_compute(&[CellId::Input(first), CellId::Input(second)], |v| {
+            format!("{} {}", v[0], v[1])
+        })
+        .unwrap();
+    assert_eq!(
+        reactor.value(CellId::Compute(greeting)),
+        Some(String::from("hello world"))
+    );
+    assert!(reactor.set_value(second, String::from("there")));
+    assert_eq!(
+        reactor.value(CellId::Compute(greeting)),
+        Some(String::from("hello there"))
+    );
+}
+
+#[test]
+fn values_can_be_borrowed_without_cloning() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(vec![1, 2, 3]);
+    let total = reactor
+        .create_compute(&[CellId::Input(input)], |v| vec![v[0].iter().sum()])
+        .unwrap();
+    assert_eq!(reactor.value_ref(CellId::Input(input)), Some(&vec![1, 2, 3]));
+    assert_eq!(reactor.value_ref(CellId::Compute(total)), Some(&vec![6]));
+    assert_eq!(Reactor::<Vec<i32>>::new().value_ref(CellId::Input(input)), None);
+    assert_eq!(
+        Reactor::<Vec<i32>>::new().value_or_default(CellId::Compute(total)),
+        Vec::<i32>::new()
+    );
+}
+
+#[test]
+fn one_shot_callbacks_fire_only_once() {
+    let once = CallbackRecorder::new();
+    let always = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let callback = reactor
+        .add_callback_once(output, |v| once.callback_called(v))
+        .unwrap();
+    assert!(reactor
+        .add_callback(output, |v| always.callback_called(v))
+        .is_some());
+
+    assert!(reactor.set_value(input, 2));
+    once.expect_to_have_been_called_with(3);
+    always.expect_to_have_been_called_with(3);
+
+    assert!(reactor.set_value(input, 3));
+    once.expect_not_to_have_been_called();
+    always.expect_to_have_been_called_with(4);
+    assert_eq!(
+        reactor.remove_callback(output, callback),
+        Err(RemoveCallbackError::NonexistentCallback)
+    );
+}
+
+#[test]
+fn callbacks_can_receive_the_previous_value() {
+    let calls = std::sync::Mutex::new(Vec::new());
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] * 10)
+        .unwrap();
+    assert!(reactor
+        .add_callback_with_prev(output, |old, new| calls.lock().unwrap().push((old, new)))
+        .is_some());
+    assert!(reactor.set_value(input, 2));
+    assert!(reactor.set_value(input, 2));
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(*calls.lock().unwrap(), vec![(10, 20), (20, 50)]);
+}
+
+#[test]
+fn global_subscribers_see_changes_in_topological_order() {
+    let log = std::sync::Mutex::new(Vec::new());
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let plus_one = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let times_two = reactor
+        .create_compute(&[CellId::Compute(plus_one)], |v| v[0] * 2)
+        .unwrap();
+    let subscription = reactor.subscribe_all(|id, v| log.lock().unwrap().push((id, v)));
+
+    assert!(reactor.set_value(input, 2));
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            (CellId::Compute(plus_one), 3),
+            (CellId::Compute(times_two), 6)
+        ]
+    );
+
+    assert!(reactor.unsubscribe_all(subscription));
+    assert!(!reactor.unsubscribe_all(subscription));
+    assert!(reactor.set_value(input, 3));
+    assert_eq!(log.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn transactions_defer_propagation_until_commit() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_input(2);
+    let sum = reactor
+        .create_compute(&[CellId::Input(a), CellId::Input(b)], |v| v[0] + v[1])
+        .unwrap();
+    assert!(reactor
+        .add_callback(sum, |v| cb.callback_called(v))
+        .is_some());
+
+    reactor.begin_transaction();
+    assert!(reactor.set_value(a, 10));
+    assert!(reactor.set_value(b, 20));
+    assert!(reactor.set_value(a, 30));
+    assert_eq!(reactor.value(CellId::Compute(sum)), Some(3));
+    cb.expect_not_to_have_been_called();
+    assert_eq!(reactor.commit(), Ok(()));
+    cb.expect_to_have_been_called_with(50);
+}
+
+#[test]
+fn rolling_back_a_transaction_restores_inputs() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    assert!(reactor
+        .add_callback(output, |v| cb.callback_called(v))
+        .is_some());
+
+    reactor.begin_transaction();
+    assert!(reactor.set_value(input, 5));
+    assert!(reactor.set_value(input, 6));
+    reactor.rollback();
+    assert_eq!(reactor.value(CellId::Input(input)), Some(1));
+    assert_eq!(reactor.value(CellId::Compute(output)), Some(2));
+    cb.expect_not_to_have_been_called();
+
+    assert!(reactor.set_value(input, 7));
+    cb.expect_to_have_been_called_with(8);
+}
+
+#[test]
+fn commit_reports_writes_undone_by_the_step_limit() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let a = reactor.create_compute(&[CellId::Input(input)], |v| v[0] + 1).unwrap();
+    reactor.create_compute(&[CellId::Compute(a)], |v| v[0] * 2).unwrap();
+    reactor.set_max_propagation_steps(Some(1));
+
+    reactor.begin_transaction();
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(reactor.commit(), Err(PropagationError::StepLimitExceeded));
+    assert_eq!(reactor.value(CellId::Input(input)), Some(1));
+    assert_eq!(reactor.commit(), Ok(()));
+}
+
+#[test]
+fn cells_removed_during_a_transaction_are_not_recomputed_on_commit() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let kept = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let leaf = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] * 2)
+        .unwrap();
+
+    reactor.begin_transaction();
+    assert_eq!(reactor.recompute_all(), Ok(()));
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(reactor.remove_cell(leaf), Ok(()));
+    assert_eq!(reactor.commit(), Ok(()));
+    assert_eq!(reactor.value(CellId::Compute(kept)), Some(6));
+}
+
+#[test]
+fn snapshots_can_be_restored() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let snap = reactor.snapshot();
+    assert!(reactor.set_value(input, 5));
+    assert!(reactor
+        .add_callback(output, |v| cb.callback_called(v))
+        .is_some());
+
+    assert_eq!(reactor.restore(&snap), Ok(()));
+    cb.expect_to_have_been_called_with(2);
+    assert_eq!(reactor.value(CellId::Input(input)), Some(1));
+    assert_eq!(reactor.value(CellId::Compute(output)), Some(2));
+}
+
+#[test]
+fn restoring_a_snapshot_restores_error_states() {
+    let mut reactor = Reactor::new();
+    let divisor = reactor.create_input(2);
+    let quotient = reactor
+        .create_try_compute(&[CellId::Input(divisor)], |v| {
+            if v[0] == 0 {
+                Err("division by zero")
+            } else {
+                Ok(100 / v[0])
+            }
+        })
+        .unwrap();
+    let valid = reactor.snapshot();
+    assert!(reactor.set_value(divisor, 0));
+    let errored = reactor.snapshot();
+    assert_eq!(reactor.value(CellId::Compute(quotient)), None);
+
+    assert_eq!(reactor.restore(&valid), Ok(()));
+    assert_eq!(reactor.value(CellId::Compute(quotient)), Some(50));
+    assert_eq!(reactor.restore(&errored), Ok(()));
+    assert_eq!(reactor.value(CellId::Compute(quotient)), None);
+}
+
+#[test]
+fn error_restoring_a_snapshot_of_a_different_graph() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let snap = reactor.snapshot();
+    let _ = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    assert_eq!(reactor.restore(&snap), Err(RestoreError::ShapeMismatch));
+}
+
+#[test]
+fn memoized_compute_cells_skip_repeated_inputs() {
+    let runs = SyncCell::new(0);
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_memo_compute(&[CellId::Input(input)], 2, |v| {
+            runs.set(runs.get() + 1);
+            v[0] * 100
+        })
+        .unwrap();
+    assert_eq!(runs.get(), 1);
+
+    for _ in 0..3 {
+        assert!(reactor.set_value(input, 2));
+        assert_eq!(reactor.value(CellId::Compute(output)), Some(200));
+        assert!(reactor.set_value(input, 1));
+        assert_eq!(reactor.value(CellId::Compute(output)), Some(100));
+    }
+    assert_eq!(runs.get(), 2);
+
+    // A third distinct input evicts the least recently used entry.
+    assert!(reactor.set_value(input, 3));
+    assert!(reactor.set_value(input, 2));
+    assert_eq!(runs.get(), 4);
+}
+
+#[test]
+fn previews_leave_memo_caches_alone() {
+    let runs = SyncCell::new(0);
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_memo_compute(&[CellId::Input(input)], 2, |v| {
+            runs.set(runs.get() + 1);
+            v[0] * 100
+        })
+        .unwrap();
+    assert!(reactor.set_value(input, 2));
+    assert_eq!(runs.get(), 2);
+
+    // Neither a cached nor an uncached preview touches the cache, so both
+    // real entries survive.
+    assert_eq!(reactor.would_change(input, 1), vec![output]);
+    assert_eq!(reactor.would_change(input, 3), vec![output]);
+    assert_eq!(runs.get(), 3);
+    assert!(reactor.set_value(input, 1));
+    assert!(reactor.set_value(input, 2));
+    assert_eq!(runs.get(), 3);
+}
+
+#[test]
+fn constant_cells_can_be_dependencies() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(2);
+    let ten = reactor.create_constant(10);
+    let output = reactor
+        .create_compute(&[CellId::Input(input), ten], |v| v[0] * v[1])
+        .unwrap();
+    assert_eq!(reactor.value(ten), Some(10));
+    assert_eq!(reactor.value(CellId::Compute(output)), Some(20));
+    assert!(reactor.set_value(input, 3));
+    assert_eq!(reactor.value(CellId::Compute(output)), Some(30));
+    assert_eq!(reactor.dependents(ten), Some(vec![CellId::Compute(output)]));
+}
+
+#[test]
+fn graphs_can_be_exported_as_dot() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let _ = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let dot = reactor.to_dot();
+    assert!(dot.starts_with("digraph reactor {\n"));
+    assert!(dot.contains("    input0 [shape=box, label=\"input0 = 1\"];\n"));
+    assert!(dot.contains("    compute0 [shape=ellipse, label=\"compute0 = 2\"];\n"));
+    assert!(dot.contains("    input0 -> compute0;\n"));
+    assert!(dot.ends_with("}\n"));
+}
+
+#[test]
+fn callbacks_fire_in_the_order_they_were_added() {
+    let log = std::sync::Mutex::new(Vec::new());
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let mut ids = Vec::new();
+    for name in ["a", "b", "c", "d", "e"] {
+        let log = &log;
+        ids.push(
+            reactor
+                .add_callback(output, move |_| log.lock().unwrap().push(name))
+                .unwrap(),
+        );
+    }
+    assert!(reactor.remove_callback(output, ids[1]).is_ok());
+    assert!(reactor.remove_callback(output, ids[3]).is_ok());
+    assert!(reactor.remove_callback(output, ids[0]).is_ok());
+    assert!(reactor
+        .add_callback(output, |_| log.lock().unwrap().push("f"))
+        .is_some());
+
+    assert!(reactor.set_value(input, 2));
+    assert_eq!(*log.lock().unwrap(), vec!["c", "e", "f"]);
+}
+
+#[test]
+fn error_removing_a_callback_through_the_wrong_cell() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let first = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let second = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] - 1)
+        .unwrap();
+    let callback = reactor
+        .add_callback(first, |v| cb.callback_called(v))
+        .unwrap();
+    let other = reactor.add_callback(second, |_| ()).unwrap();
+    assert_ne!(callback, other);
+    assert_eq!(
+        reactor.remove_callback(second, callback),
+        Err(RemoveCallbackError::NonexistentCallback)
+    );
+    assert!(reactor.set_value(input, 2));
+    cb.expect_to_have_been_called_with(3);
+}
+
+#[test]
+fn error_removing_the_same_callback_twice() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let callback = reactor.add_callback(output, |_| ()).unwrap();
+    assert_eq!(reactor.remove_callback(output, callback), Ok(()));
+    assert_eq!(
+        reactor.remove_callback(output, callback),
+        Err(RemoveCallbackError::NonexistentCallback)
+    );
+}
+
+#[test]
+fn all_cells_can_be_enumerated() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let cells: Vec<(CellId, i32)> = reactor.iter_cells().collect();
+    assert_eq!(cells.len(), 2);
+    assert!(cells.contains(&(CellId::Input(input), 1)));
+    assert!(cells.contains(&(CellId::Compute(output), 2)));
+    assert_eq!(reactor.iter_cells().collect::<Vec<_>>(), cells);
+}
+
+#[test]
+fn forked_reactors_evolve_independently() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    assert!(reactor
+        .add_callback(output, |v| cb.callback_called(v))
+        .is_some());
+
+    let mut fork = reactor.fork();
+    assert!(fork.set_value(input, 10));
+    assert_eq!(fork.value(CellId::Compute(output)), Some(11));
+    // The fork shares the callback with the original reactor.
+    cb.expect_to_have_been_called_with(11);
+    assert_eq!(reactor.value(CellId::Compute(output)), Some(2));
+
+    assert!(reactor.set_value(input, 5));
+    cb.expect_to_have_been_called_with(6);
+    assert_eq!(fork.value(CellId::Compute(output)), Some(11));
+}
+
+#[test]
+fn unchanged_cells_do_not_recompute_their_children() {
+    let runs = SyncCell::new(0);
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let is_big = reactor
+        .create_compute(&[CellId::Input(input)], |v| if v[0] > 100 { 1 } else { 0 })
+        .unwrap();
+    let mut outputs = Vec::new();
+    for i in 0..10 {
+        let runs = &runs;
+        outputs.push(
+            reactor
+                .create_compute(&[CellId::Compute(is_big)], move |v| {
+                    runs.set(runs.get() + 1);
+                    v[0] + i
+                })
+                .unwrap(),
+        );
+    }
+    assert_eq!(runs.get(), 10);
+
+    assert!(reactor.set_value(input, 50));
+    assert_eq!(runs.get(), 10);
+
+    assert!(reactor.set_value(input, 500));
+    assert_eq!(runs.get(), 20);
+    assert_eq!(reactor.value(CellId::Compute(outputs[3])), Some(4));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn failed_initial_computation_leaves_no_wiring_behind() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(0);
+    assert_eq!(
+        reactor.try_create_compute(&[CellId::Input(input)], |v| 10 / v[0]),
+        Err(CreateCellError::ComputeFailed)
+    );
+    assert_eq!(reactor.dependents(CellId::Input(input)), Some(vec![]));
+    assert!(reactor.set_value(input, 2));
+
+    let output = reactor
+        .try_create_compute(&[CellId::Input(input)], |v| 10 / v[0])
+        .unwrap();
+    assert_eq!(reactor.value(CellId::Compute(output)), Some(5));
+}
+
+#[test]
+fn fallible_compute_cells_carry_errors_downstream() {
+    #[derive(Debug, PartialEq)]
+    struct DivideByZero;
+
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let divisor = reactor.create_input(2);
+    let quotient = reactor
+        .create_try_compute(&[CellId::Input(divisor)], |v| {
+            if v[0] == 0 {
+                Err(DivideByZero)
+            } else {
+                Ok(100 / v[0])
+            }
+        })
+        .unwrap();
+    let plus_one = reactor
+        .create_compute(&[CellId::Compute(quotient)], |v| v[0] + 1)
+        .unwrap();
+    assert!(reactor
+        .add_callback(plus_one, |v| cb.callback_called(v))
+        .is_some());
+    assert_eq!(reactor.value(CellId::Compute(plus_one)), Some(51));
+
+    assert!(reactor.set_value(divisor, 0));
+    assert_eq!(reactor.value(CellId::Compute(quotient)), None);
+    assert_eq!(reactor.value(CellId::Compute(plus_one)), None);
+    assert_eq!(reactor.error::<DivideByZero>(quotient), Some(&DivideByZero));
+    assert_eq!(reactor.error::<DivideByZero>(plus_one), Some(&DivideByZero));
+    cb.expect_not_to_have_been_called();
+
+    assert!(reactor.set_value(divisor, 4));
+    assert_eq!(reactor.error::<DivideByZero>(quotient), None);
+    assert_eq!(reactor.value(CellId::Compute(plus_one)), Some(26));
+    cb.expect_to_have_been_called_with(26);
+}
+
+#[test]
+fn recovering_to_the_last_good_value_fires_callbacks() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let divisor = reactor.create_input(2);
+    let quotient = reactor
+        .create_try_compute(&[CellId::Input(divisor)], |v| match v[0] {
+            0 => Err("divide by zero"),
+            d => Ok(100 / d),
+        })
+        .unwrap();
+    assert!(reactor.add_callback(quotient, |v| cb.callback_called(v)).is_some());
+
+    assert!(reactor.set_value(divisor, 0));
+    cb.expect_not_to_have_been_called();
+    assert!(reactor.set_value(divisor, 2));
+    cb.expect_to_have_been_called_with(50);
+    assert_eq!(reactor.value(CellId::Compute(quotient)), Some(50));
+}
+
+#[test]
+fn lazy_reactors_compute_on_read() {
+    let runs = SyncCell::new(0);
+    let cb = CallbackRecorder::new();
+    let mut reactor = LazyReactor::new();
+    let input = reactor.create_input(1);
+    let plus_one = reactor
+        .create_compute(&[CellId::Input(input)], |v| {
+            runs.set(runs.get() + 1);
+            v[0] + 1
+        })
+        .unwrap();
+    let times_two = reactor
+        .create_compute(&[CellId::Compute(plus_one)], |v| v[0] * 2)
+        .unwrap();
+    assert!(reactor
+        .add_callback(times_two, |v| cb.callback_called(v))
+        .is_some());
+
+    for i in 2..6 {
+        assert!(reactor.set_value(input, i));
+    }
+    assert_eq!(runs.get(), 1);
+    assert!(reactor.is_dirty(times_two));
+    cb.expect_not_to_have_been_called();
+
+    assert_eq!(reactor.value(CellId::Compute(times_two)), Some(12));
+    assert_eq!(runs.get(), 2);
+    assert!(!reactor.is_dirty(plus_one));
+    cb.expect_to_have_been_called_with(12);
+
+    assert_eq!(reactor.value(CellId::Compute(times_two)), Some(12));
+    assert_eq!(runs.get(), 2);
+    cb.expect_not_to_have_been_called();
+}
+
+#[test]
+fn cell_counts_track_the_graph_size() {
+    let mut reactor = Reactor::new();
+    assert_eq!((reactor.input_count(), reactor.compute_count()), (0, 0));
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    assert_eq!((reactor.input_count(), reactor.compute_count()), (1, 1));
+    assert!(reactor.remove_cell(output).is_ok());
+    assert_eq!((reactor.input_count(), reactor.compute_count()), (1, 0));
+}
+
+#[test]
+fn error_creating_compute_cell_without_dependencies() {
+    let mut reactor = Reactor::<i32>::new();
+    assert_eq!(
+        reactor.create_compute(&[], |_| 0),
+        Err(CreateCellError::NoDependencies)
+    );
+    assert_eq!(reactor.compute_count(), 0);
+}
+
+#[test]
+fn inputs_can_be_reset_to_their_initial_values() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_input(2);
+    let sum = reactor
+        .create_compute(&[CellId::Input(a), CellId::Input(b)], |v| v[0] + v[1])
+        .unwrap();
+    assert!(reactor.set_value(a, 10));
+    assert!(reactor.set_value(b, 20));
+    assert!(reactor
+        .add_callback(sum, |v| cb.callback_called(v))
+        .is_some());
+
+    assert_eq!(reactor.reset_inputs(), Ok(()));
+    assert_eq!(reactor.value(CellId::Input(a)), Some(1));
+    assert_eq!(reactor.value(CellId::Input(b)), Some(2));
+    cb.expect_to_have_been_called_with(3);
+}
+
+#[test]
+fn topological_order_puts_parents_before_children() {
+    assert_eq!(Reactor::<i32>::new().topological_order(), vec![]);
+
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let one = reactor.create_constant(1);
+    let a = reactor
+        .create_compute(&[CellId::Input(input), one], |v| v[0] + v[1])
+        .unwrap();
+    let b = reactor
+        .create_compute(&[CellId::Compute(a)], |v| v[0] * 2)
+        .unwrap();
+    let c = reactor
+        .create_compute(&[CellId::Compute(b), CellId::Compute(a)], |v| v[0] - v[1])
+        .unwrap();
+    let order = reactor.topological_order();
+    assert_eq!(order.len(), 5);
+    let pos = |id| order.iter().position(|&c| c == id).unwrap();
+    for &(parent, child) in &[
+        (CellId::Input(input), CellId::Compute(a)),
+        (one, CellId::Compute(a)),
+        (CellId::Compute(a), CellId::Compute(b)),
+        (CellId::Compute(b), CellId::Compute(c)),
+        (CellId::Compute(a), CellId::Compute(c)),
+    ] {
+        assert!(pos(parent) < pos(child));
+    }
+}
+
+#[test]
+fn recompute_all_refreshes_stale_cells() {
+    let factor = SyncCell::new(2);
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(3);
+    let scaled = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] * factor.get())
+        .unwrap();
+    let plus_one = reactor
+        .create_compute(&[CellId::Compute(scaled)], |v| v[0] + 1)
+        .unwrap();
+    assert!(reactor
+        .add_callback(plus_one, |v| cb.callback_called(v))
+        .is_some());
+
+    factor.set(10);
+    assert_eq!(reactor.value(CellId::Compute(plus_one)), Some(7));
+    assert_eq!(reactor.recompute_all(), Ok(()));
+    assert_eq!(reactor.value(CellId::Compute(plus_one)), Some(31));
+    cb.expect_to_have_been_called_with(31);
+
+    assert_eq!(reactor.recompute_all(), Ok(()));
+    cb.expect_not_to_have_been_called();
+}
+
+#[test]
+fn higher_priority_callbacks_fire_first() {
+    let log = std::sync::Mutex::new(Vec::new());
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    assert!(reactor
+        .add_callback(output, |_| log.lock().unwrap().push("paint"))
+        .is_some());
+    assert!(reactor
+        .add_callback_with_priority(output, 10, |_| log.lock().unwrap().push("layout"))
+        .is_some());
+    assert!(reactor
+        .add_callback_with_priority(output, 0, |_| log.lock().unwrap().push("overlay"))
+        .is_some());
+    assert!(reactor
+        .add_callback_with_priority(output, -5, |_| log.lock().unwrap().push("log"))
+        .is_some());
+
+    assert!(reactor.set_value(input, 2));
+    assert_eq!(*log.lock().unwrap(), vec!["layout", "paint", "overlay", "log"]);
+}
+
+#[test]
+fn sum_and_product_cells() {
+    let mut reactor = Reactor::new();
+    let inputs: Vec<CellId> = (1..=4).map(|i| CellId::Input(reactor.create_input(i))).collect();
+    let sum = reactor.create_sum(&inputs).unwrap();
+    let product = reactor.create_product(&inputs).unwrap();
+    assert_eq!(reactor.value(CellId::Compute(sum)), Some(10));
+    assert_eq!(reactor.value(CellId::Compute(product)), Some(24));
+    assert_eq!(reactor.create_sum(&[]), Err(CreateCellError::NoDependencies));
+    assert_eq!(reactor.create_product(&[]), Err(CreateCellError::NoDependencies));
+}
+
+#[test]
+fn map_combinators_pass_positional_arguments() {
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(10);
+    let b = reactor.create_input(3);
+    let doubled = reactor.map(CellId::Input(a), |x| x * 2).unwrap();
+    let difference = reactor
+        .map2(CellId::Compute(doubled), CellId::Input(b), |x, y| x - y)
+        .unwrap();
+    assert_eq!(reactor.value(CellId::Compute(difference)), Some(17));
+    assert!(reactor.set_value(b, 5));
+    assert_eq!(reactor.value(CellId::Compute(difference)), Some(15));
+}
+
+#[test]
+fn generations_count_propagations_that_change_something() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let parity = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] % 2)
+        .unwrap();
+    assert_eq!(reactor.generation(), 0);
+    assert_eq!(reactor.last_changed_generation(parity), Some(0));
+
+    assert!(reactor.set_value(input, 1));
+    assert_eq!(reactor.generation(), 0);
+
+    assert!(reactor.set_value(input, 3));
+    assert_eq!(reactor.generation(), 1);
+    assert_eq!(reactor.last_changed_generation(parity), Some(0));
+
+    assert!(reactor.set_value_batch(&[(input, 4)]));
+    assert_eq!(reactor.generation(), 2);
+    assert_eq!(reactor.last_changed_generation(parity), Some(2));
+}
+
+#[test]
+fn reactors_can_be_default_constructed() {
+    #[derive(Default)]
+    struct Model<'a> {
+        reactor: Reactor<'a, i32>,
+    }
+
+    let mut model = Model::default();
+    let input = model.reactor.create_input(7);
+    assert_eq!(model.reactor.value(CellId::Input(input)), Some(7));
+}
+
+#[test]
+fn builders_resolve_names_declared_in_any_order() {
+    let mut builder = ReactorBuilder::new();
+    builder
+        .compute("total", &["subtotal", "tax"], |v| v[0] + v[1])
+        .compute("tax", &["subtotal"], |v| v[0] / 10)
+        .compute("subtotal", &["a", "b"], |v| v[0] + v[1])
+        .input("a", 60)
+        .input("b", 40);
+    let (mut reactor, ids) = builder.build_with_ids().unwrap();
+    assert_eq!(reactor.value(ids["total"]), Some(110));
+
+    let CellId::Input(a) = ids["a"] else {
+        panic!("a should be an input");
+    };
+    assert!(reactor.set_value(a, 160));
+    assert_eq!(reactor.value(ids["total"]), Some(220));
+}
+
+#[test]
+fn builders_report_invalid_graphs() {
+    let mut builder = ReactorBuilder::new();
+    builder.input("a", 1).compute("b", &["a", "c"], |v| v[0] + v[1]);
+    assert_eq!(
+        builder.build().err(),
+        Some(BuildError::MissingReference {
+            cell: "b".to_string(),
+            missing: "c".to_string()
+        })
+    );
+
+    let mut builder = ReactorBuilder::new();
+    builder
+        .input("a", 1)
+        .compute("b", &["a", "d"], |v| v[0] + v[1])
+        .compute("c", &["b"], |v| v[0])
+        .compute("d", &["c"], |v| v[0]);
+    assert_eq!(
+        builder.build().err(),
+        Some(BuildError::Cycle(vec![
+            "b".to_string(),
+            "d".to_string(),
+            "c".to_string(),
+            "b".to_string()
+        ]))
+    );
+
+    let mut builder = ReactorBuilder::new();
+    builder.input("a", 1).input("a", 2);
+    assert_eq!(
+        builder.build().err(),
+        Some(BuildError::DuplicateName("a".to_string()))
+    );
+}
+
+#[test]
+fn graphs_stay_consistent_through_edits() {
+    let mut reactor = Reactor::new();
+    assert_eq!(reactor.validate(), Ok(()));
+    let input = reactor.create_input(1);
+    let one = reactor.create_constant(1);
+    let left = reactor
+        .create_compute(&[CellId::Input(input), one], |v| v[0] + v[1])
+        .unwrap();
+    let right = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] * 2)
+        .unwrap();
+    let output = reactor
+        .create_compute(&[CellId::Compute(left), CellId::Compute(right)], |v| {
+            v[0] * v[1]
+        })
+        .unwrap();
+    assert_eq!(reactor.validate(), Ok(()));
+    assert!(reactor.set_value(input, 3));
+    assert_eq!(reactor.value(CellId::Compute(output)), Some(24));
+    assert!(reactor.remove_cell(output).is_ok());
+    assert_eq!(reactor.validate(), Ok(()));
+}
+
+#[test]
+fn setting_an_unchanged_value_skips_propagation() {
+    let runs = SyncCell::new(0);
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let _ = reactor
+        .create_compute(&[CellId::Input(input)], |v| {
+            runs.set(runs.get() + 1);
+            v[0] + 1
+        })
+        .unwrap();
+    assert!(reactor.set_value(input, 1));
+    assert_eq!(runs.get(), 1);
+    assert_eq!(reactor.set_value_changed(input, 1), Some(false));
+    assert_eq!(reactor.set_value_changed(input, 2), Some(true));
+    assert_eq!(runs.get(), 2);
+    assert_eq!(Reactor::new().set_value_changed(input, 2), None);
+}
+
+#[test]
+fn input_callbacks_fire_before_compute_callbacks() {
+    let log = std::sync::Mutex::new(Vec::new());
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] * 10)
+        .unwrap();
+    assert!(reactor
+        .add_callback(output, |v| log.lock().unwrap().push(("output", v)))
+        .is_some());
+    let callback = reactor
+        .add_input_callback(input, |v| log.lock().unwrap().push(("input", v)))
+        .unwrap();
+
+    assert!(reactor.set_value(input, 2));
+    assert!(reactor.set_value(input, 2));
+    assert_eq!(*log.lock().unwrap(), vec![("input", 2), ("output", 20)]);
+
+    assert_eq!(reactor.remove_input_callback(input, callback), Ok(()));
+    assert!(reactor.set_value(input, 3));
+    assert_eq!(log.lock().unwrap().last(), Some(&("output", 30)));
+    assert_eq!(log.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn blueprints_rehydrate_with_supplied_functions() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(2);
+    let ten = reactor.create_constant(10);
+    let output = reactor
+        .create_compute(&[CellId::Input(input), ten], |v| v[0] * v[1])
+        .unwrap();
+    assert!(reactor.set_value(input, 3));
+    let blueprint = reactor.blueprint();
+
+    let mut funcs: HashMap<ComputeCellId, BoxedComputeFn<i32>> =
+        HashMap::new();
+    funcs.insert(output, Box::new(|v: &[i32]| v[0] * v[1]));
+    let mut restored = Reactor::rehydrate(&blueprint, funcs).unwrap();
+    assert_eq!(restored.value(CellId::Compute(output)), Some(30));
+    assert!(restored.set_value(input, 4));
+    assert_eq!(restored.value(CellId::Compute(output)), Some(40));
+
+    let mut funcs: HashMap<ComputeCellId, BoxedComputeFn<i32>> =
+        HashMap::new();
+    funcs.insert(output, Box::new(|v: &[i32]| v[0] + v[1]));
+    assert_eq!(
+        Reactor::rehydrate(&blueprint, funcs).err(),
+        Some(RehydrateError::Inconsistent(output))
+    );
+    assert_eq!(
+        Reactor::rehydrate(&blueprint, HashMap::new()).err(),
+        Some(RehydrateError::MissingFunction(output))
+    );
+}
+
+#[test]
+fn rehydrated_reactors_never_reissue_removed_ids() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let kept = reactor.create_compute(&[CellId::Input(input)], |v| v[0]).unwrap();
+    let removed = reactor.create_compute(&[CellId::Input(input)], |v| v[0]).unwrap();
+    reactor.remove_cell(removed).unwrap();
+
+    let mut funcs: HashMap<ComputeCellId, BoxedComputeFn<i32>> =
+        HashMap::new();
+    funcs.insert(kept, Box::new(|v: &[i32]| v[0]));
+    let mut restored = Reactor::rehydrate(&reactor.blueprint(), funcs).unwrap();
+    let fresh = restored.create_compute(&[CellId::Input(input)], |v| v[0]).unwrap();
+    assert_ne!(fresh, removed);
+}
+
+#[test]
+fn affected_cells_lists_downstream_compute_cells_in_order() {
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_input(2);
+    let plus_one = reactor.create_compute(&[CellId::Input(a)], |v| v[0] + 1).unwrap();
+    let sum = reactor
+        .create_compute(&[CellId::Compute(plus_one), CellId::Input(b)], |v| v[0] + v[1])
+        .unwrap();
+    let only_b = reactor.create_compute(&[CellId::Input(b)], |v| v[0] * 2).unwrap();
+
+    assert_eq!(reactor.affected_cells(a), vec![plus_one, sum]);
+    let from_b = reactor.affected_cells(b);
+    assert_eq!(from_b.len(), 2);
+    assert!(from_b.contains(&sum) && from_b.contains(&only_b));
+}
+
+#[test]
+fn replace_compute_func_recomputes_and_keeps_callbacks() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(2);
+    let doubled = reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 2).unwrap();
+    let plus_one = reactor
+        .create_compute(&[CellId::Compute(doubled)], |v| v[0] + 1)
+        .unwrap();
+    assert!(reactor.add_callback(plus_one, |v| cb.callback_called(v)).is_some());
+
+    assert!(reactor.replace_compute_func(doubled, |v| v[0] * 10));
+    assert_eq!(reactor.value(CellId::Compute(doubled)), Some(20));
+    cb.expect_to_have_been_called_with(21);
+
+    assert!(reactor.set_value(input, 3));
+    cb.expect_to_have_been_called_with(31);
+
+    assert!(reactor.replace_compute_func(doubled, |v| v[0] * 10));
+    cb.expect_not_to_have_been_called();
+
+    let mut other = Reactor::new();
+    let input = other.create_input(0);
+    other.create_compute(&[CellId::Input(input)], |v| v[0]).unwrap();
+    other.create_compute(&[CellId::Input(input)], |v| v[0]).unwrap();
+    let unknown = other.create_compute(&[CellId::Input(input)], |v| v[0]).unwrap();
+    assert!(!reactor.replace_compute_func(unknown, |v| v[0]));
+}
+
+#[test]
+fn compute_ids_are_not_reused_after_removal() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let first = reactor.create_compute(&[CellId::Input(input)], |v| v[0]).unwrap();
+    let second = reactor.create_compute(&[CellId::Input(input)], |v| v[0] + 1).unwrap();
+    assert!(reactor.remove_cell(second).is_ok());
+
+    let third = reactor.create_compute(&[CellId::Input(input)], |v| v[0] + 2).unwrap();
+    assert_ne!(third, second);
+    assert_ne!(third, first);
+    assert_eq!(reactor.value(CellId::Compute(second)), None);
+    assert_eq!(reactor.value(CellId::Compute(third)), Some(3));
+}
+
+#[test]
+fn propagation_step_limit_rejects_large_updates() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let mut last = CellId::Input(input);
+    for _ in 0..3 {
+        last = CellId::Compute(reactor.create_compute(&[last], |v| v[0] + 1).unwrap());
+    }
+
+    reactor.set_max_propagation_steps(Some(2));
+    assert_eq!(
+        reactor.try_set_value(input, 5),
+        Err(PropagationError::StepLimitExceeded)
+    );
+    assert_eq!(reactor.value(CellId::Input(input)), Some(1));
+    assert_eq!(reactor.value(last), Some(4));
+    assert!(!reactor.set_value(input, 5));
+
+    reactor.set_max_propagation_steps(Some(3));
+    assert_eq!(reactor.try_set_value(input, 5), Ok(true));
+    assert_eq!(reactor.value(last), Some(8));
+}
+
+#[test]
+fn structural_changes_over_the_step_limit_are_undone() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let a = CellId::Input(input);
+    let b = CellId::Input(reactor.create_input(2));
+    let total = reactor.create_compute(&[a], |v| v.iter().sum()).unwrap();
+    reactor.set_max_propagation_steps(Some(0));
+
+    assert_eq!(
+        reactor.add_dependency(total, b),
+        Err(CreateCellError::Propagation(PropagationError::StepLimitExceeded))
+    );
+    assert_eq!(reactor.dependencies(CellId::Compute(total)), Some(vec![a]));
+    assert_eq!(reactor.dependents(b), Some(vec![]));
+    assert_eq!(
+        reactor.repoint_dependency(total, a, b),
+        Err(CreateCellError::Propagation(PropagationError::StepLimitExceeded))
+    );
+    assert_eq!(reactor.dependencies(CellId::Compute(total)), Some(vec![a]));
+    assert_eq!(reactor.dependents(a), Some(vec![CellId::Compute(total)]));
+    assert!(!reactor.replace_compute_func(total, |v| v[0] * 10));
+    assert_eq!(reactor.recompute_all(), Err(PropagationError::StepLimitExceeded));
+    assert_eq!(reactor.value(CellId::Compute(total)), Some(1));
+
+    reactor.set_max_propagation_steps(None);
+    assert_eq!(reactor.add_dependency(total, b), Ok(()));
+    reactor.set_max_propagation_steps(Some(0));
+    assert!(!reactor.remove_dependency(total, a));
+    assert_eq!(reactor.dependencies(CellId::Compute(total)), Some(vec![a, b]));
+    assert_eq!(reactor.dependents(a), Some(vec![CellId::Compute(total)]));
+    assert!(reactor.freeze(total));
+    assert!(!reactor.unfreeze(total));
+    reactor.set_max_propagation_steps(None);
+    assert!(reactor.set_value(input, 5));
+    assert_eq!(reactor.value(CellId::Compute(total)), Some(3));
+}
+
+#[test]
+fn kind_reflects_cells_that_exist() {
+    let mut reactor = Reactor::new();
+    let input = CellId::Input(reactor.create_input(1));
+    let constant = reactor.create_constant(2);
+    let output = reactor.create_compute(&[input, constant], |v| v[0] + v[1]).unwrap();
+    let output = CellId::Compute(output);
+
+    assert_eq!(reactor.kind(input), Some(CellKind::Input));
+    assert_eq!(reactor.kind(constant), Some(CellKind::Constant));
+    assert_eq!(reactor.kind(output), Some(CellKind::Compute));
+    assert!(reactor.is_input(input) && !reactor.is_compute(input));
+    assert!(reactor.is_compute(output) && !reactor.is_input(output));
+
+    let other = Reactor::<i32>::new();
+    assert_eq!(other.kind(output), None);
+    assert!(!other.is_compute(output));
+}
+
+#[test]
+fn queued_notifications_are_drained_instead_of_fired() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 2).unwrap();
+    let remainder = reactor.create_compute(&[CellId::Compute(output)], |v| v[0] % 4).unwrap();
+    assert!(reactor.add_callback(output, |v| cb.callback_called(v)).is_some());
+
+    reactor.set_queue_notifications(true);
+    assert!(reactor.set_value(input, 3));
+    cb.expect_not_to_have_been_called();
+    assert!(reactor.set_value(input, 4));
+    assert_eq!(
+        reactor.drain_notifications(),
+        vec![
+            (CellId::Input(input), 3),
+            (CellId::Compute(output), 6),
+            (CellId::Input(input), 4),
+            (CellId::Compute(output), 8),
+            (CellId::Compute(remainder), 0),
+        ]
+    );
+    assert!(reactor.drain_notifications().is_empty());
+
+    reactor.set_queue_notifications(false);
+    assert!(reactor.set_value(input, 5));
+    cb.expect_to_have_been_called_with(10);
+    assert!(reactor.drain_notifications().is_empty());
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Num(f64),
+    Bool(bool),
+    Text(String),
+}
+
+#[test]
+fn reactor_works_with_a_non_copy_sum_type() {
+    let cb = std::sync::Mutex::new(Vec::new());
+    let mut reactor = Reactor::new();
+    let price = reactor.create_input(Value::Num(2.5));
+    let quantity = reactor.create_input(Value::Num(4.0));
+    let taxed = reactor.create_input(Value::Bool(false));
+    let label = reactor
+        .create_compute(
+            &[CellId::Input(price), CellId::Input(quantity), CellId::Input(taxed)],
+            |v| match (&v[0], &v[1], &v[2]) {
+                (Value::Num(p), Value::Num(q), Value::Bool(t)) => {
+                    let total = if *t { p * q * 1.2 } else { p * q };
+                    Value::Text(format!("{total:.2}"))
+                }
+                _ => Value::Text("n/a".to_string()),
+            },
+        )
+        .unwrap();
+    assert!(reactor.add_callback(label, |v| cb.lock().unwrap().push(v)).is_some());
+
+    assert_eq!(
+        reactor.value(CellId::Compute(label)),
+        Some(Value::Text("10.00".to_string()))
+    );
+    assert!(reactor.set_value(taxed, Value::Bool(true)));
+    assert!(reactor.set_value(quantity, Value::Text("four".to_string())));
+    assert_eq!(
+        *cb.lock().unwrap(),
+        vec![Value::Text("12.00".to_string()), Value::Text("n/a".to_string())]
+    );
+}
+
+#[test]
+fn cell_info_collects_a_cells_state() {
+    let mut reactor = Reactor::new();
+    let input = CellId::Input(reactor.create_input(1));
+    let constant = reactor.create_constant(10);
+    let sum = reactor.create_compute(&[input, constant], |v| v[0] + v[1]).unwrap();
+    let doubled = reactor.create_compute(&[CellId::Compute(sum)], |v| v[0] * 2).unwrap();
+    assert!(reactor.add_callback(sum, |_| ()).is_some());
+    assert!(reactor.add_callback(sum, |_| ()).is_some());
+
+    let info = reactor.cell_info(CellId::Compute(sum)).unwrap();
+    assert_eq!(info.value(), Some(&11));
+    assert_eq!(info.parents(), &[input, constant]);
+    assert_eq!(info.children(), &[CellId::Compute(doubled)]);
+    assert_eq!(info.callback_count(), 2);
+
+    let info = reactor.cell_info(constant).unwrap();
+    assert_eq!(info.value(), Some(&10));
+    assert!(info.parents().is_empty());
+    assert_eq!(info.callback_count(), 0);
+
+    reactor.remove_cell(doubled).unwrap();
+    assert_eq!(reactor.cell_info(CellId::Compute(doubled)), None);
+}
+
+#[test]
+fn callbacks_lists_the_registered_ids_in_order() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] + 1).unwrap();
+    let first = reactor.add_callback(output, |_| ()).unwrap();
+    let once = reactor.add_callback_once(output, |_| ()).unwrap();
+    let last = reactor.add_callback(output, |_| ()).unwrap();
+    assert_eq!(reactor.callbacks(output), Some(vec![first, once, last]));
+
+    assert!(reactor.remove_callback(output, first).is_ok());
+    assert!(reactor.set_value(input, 2));
+    assert_eq!(reactor.callbacks(output), Some(vec![last]));
+
+    reactor.remove_cell(output).unwrap();
+    assert_eq!(reactor.callbacks(output), None);
+}
+
+#[test]
+fn dependencies_can_be_added_and_removed_at_runtime() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let a = CellId::Input(reactor.create_input(1));
+    let b = CellId::Input(reactor.create_input(2));
+    let total = reactor.create_compute(&[a], |v| v.iter().sum()).unwrap();
+    assert!(reactor.add_callback(total, |v| cb.callback_called(v)).is_some());
+
+    assert_eq!(reactor.add_dependency(total, b), Ok(()));
+    cb.expect_to_have_been_called_with(3);
+    assert_eq!(reactor.dependents(b), Some(vec![CellId::Compute(total)]));
+
+    let downstream = reactor.create_compute(&[CellId::Compute(total)], |v| v[0]).unwrap();
+    assert!(matches!(
+        reactor.add_dependency(total, CellId::Compute(downstream)),
+        Err(CreateCellError::Cycle(_))
+    ));
+
+    assert!(reactor.remove_dependency(total, a));
+    cb.expect_to_have_been_called_with(2);
+    assert_eq!(reactor.dependents(a), Some(vec![]));
+    assert!(!reactor.remove_dependency(total, a));
+    assert!(!reactor.remove_dependency(total, b));
+    assert_eq!(reactor.dependencies(CellId::Compute(total)), Some(vec![b]));
+}
+
+#[test]
+fn fold_min_and_max_cells_track_their_dependencies() {
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(3);
+    let b = reactor.create_input(7);
+    let deps = [CellId::Input(a), CellId::Input(b)];
+    let weighted = reactor.create_fold(&deps, 100, |acc, x| acc - x).unwrap();
+    let min = reactor.create_min(&deps).unwrap();
+    let max = reactor.create_max(&deps).unwrap();
+    assert_eq!(reactor.value(CellId::Compute(weighted)), Some(90));
+    assert_eq!(reactor.value(CellId::Compute(min)), Some(3));
+    assert_eq!(reactor.value(CellId::Compute(max)), Some(7));
+
+    assert!(reactor.set_value(a, 10));
+    assert_eq!(reactor.value(CellId::Compute(weighted)), Some(83));
+    assert_eq!(reactor.value(CellId::Compute(min)), Some(7));
+    assert_eq!(reactor.value(CellId::Compute(max)), Some(10));
+}
+
+#[test]
+fn try_add_callback_reports_nonexistent_cells() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] + 1).unwrap();
+    let id = reactor.try_add_callback(output, |_| ()).unwrap();
+    assert_eq!(reactor.callbacks(output), Some(vec![id]));
+
+    reactor.remove_cell(output).unwrap();
+    assert_eq!(
+        reactor.try_add_callback(output, |_| ()),
+        Err(AddCallbackError::NonexistentCell)
+    );
+    assert_eq!(reactor.add_callback(output, |_| ()), None);
+}
+
+#[test]
+fn propagation_metrics_describe_the_last_update() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let parity = reactor.create_compute(&[CellId::Input(input)], |v| v[0] % 2).unwrap();
+    let doubled = reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 2).unwrap();
+    assert!(reactor.add_callback(parity, |_| ()).is_some());
+    assert!(reactor.add_callback(doubled, |_| ()).is_some());
+    assert!(reactor.add_callback(doubled, |_| ()).is_some());
+    assert_eq!(reactor.last_propagation_metrics(), None);
+
+    assert!(reactor.set_value(input, 3));
+    let metrics = reactor.last_propagation_metrics().unwrap();
+    assert_eq!(metrics.visited(), 2);
+    assert_eq!(metrics.changed(), 1);
+    assert_eq!(metrics.callbacks_fired(), 2);
+
+    assert!(reactor.set_value(input, 4));
+    let metrics = reactor.last_propagation_metrics().unwrap();
+    assert_eq!((metrics.changed(), metrics.callbacks_fired()), (2, 3));
+}
+
+#[test]
+fn debug_dump_lists_cells_in_topological_order() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] + 1).unwrap();
+    assert!(reactor.add_callback(output, |_| ()).is_some());
+    assert_eq!(
+        reactor.debug_dump(),
+        "input0 input value=1 parents=[] children=[compute0] callbacks=0\n\
+         compute0 compute value=2 parents=[input0] children=[] callbacks=1\n"
+    );
+}
+
+#[test]
+fn values_equal_compares_identically_built_reactors() {
+    fn build() -> (Reactor<'static, i32>, InputCellId) {
+        let mut reactor = Reactor::new();
+        let input = reactor.create_input(1);
+        reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 3).unwrap();
+        (reactor, input)
+    }
+    let (mut a, input) = build();
+    let (mut b, _) = build();
+    assert!(a.add_callback_with_prev(a.affected_cells(input)[0], |_, _| ()).is_some());
+    assert!(a.values_equal(&b));
+
+    assert!(a.set_value(input, 2));
+    assert!(!a.values_equal(&b));
+    assert!(b.set_value(input, 2));
+    assert!(a.values_equal(&b));
+
+    b.create_constant(0);
+    assert!(!a.values_equal(&b));
+}
+
+#[cfg(feature = "async")]
+mod changes {
+    use futures_core::Stream;
+    use react::*;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn changed_resolves_on_the_next_change_only() {
+        let wakes = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(Arc::clone(&wakes));
+        let mut cx = Context::from_waker(&waker);
+        let mut reactor = Reactor::new();
+        let input = reactor.create_input(1);
+        let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 2).unwrap();
+
+        let mut next = reactor.changed(output).unwrap();
+        assert_eq!(Pin::new(&mut next).poll(&mut cx), Poll::Pending);
+        assert!(reactor.set_value(input, 2));
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+        assert_eq!(Pin::new(&mut next).poll(&mut cx), Poll::Ready(4));
+        assert_eq!(reactor.callbacks(output), Some(vec![]));
+    }
+
+    #[test]
+    fn changes_yields_every_value() {
+        let waker = Waker::from(Arc::new(CountingWaker(AtomicUsize::new(0))));
+        let mut cx = Context::from_waker(&waker);
+        let mut reactor = Reactor::new();
+        let input = reactor.create_input(1);
+        let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 2).unwrap();
+
+        let mut stream = reactor.changes(output).unwrap();
+        assert!(reactor.set_value(input, 2));
+        assert!(reactor.set_value(input, 3));
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(Some(4)));
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(Some(6)));
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Pending);
+    }
+
+    #[test]
+    fn forks_do_not_feed_futures_or_streams() {
+        let waker = Waker::from(Arc::new(CountingWaker(AtomicUsize::new(0))));
+        let mut cx = Context::from_waker(&waker);
+        let mut reactor = Reactor::new();
+        let input = reactor.create_input(1);
+        let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 2).unwrap();
+
+        let mut next = reactor.changed(output).unwrap();
+        let mut stream = reactor.changes(output).unwrap();
+        let mut fork = reactor.fork();
+        assert!(fork.set_value(input, 5));
+        assert_eq!(Pin::new(&mut next).poll(&mut cx), Poll::Pending);
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Pending);
+        assert!(reactor.set_value(input, 2));
+        assert_eq!(Pin::new(&mut next).poll(&mut cx), Poll::Ready(4));
+        assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(Some(4)));
+    }
+
+    #[test]
+    fn dropped_streams_release_their_callbacks() {
+        let mut reactor = Reactor::new();
+        let input = reactor.create_input(1);
+        let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 2).unwrap();
+        reactor.set_callback_limit(1);
+
+        for _ in 0..3 {
+            drop(reactor.changes(output).unwrap());
+        }
+        assert!(reactor.set_value(input, 2));
+        assert_eq!(reactor.callbacks(output), Some(vec![]));
+    }
+}
+
+#[test]
+fn compact_renumbers_cells_and_keeps_wiring_and_callbacks() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let scratch = reactor.create_compute(&[CellId::Input(input)], |v| v[0]).unwrap();
+    let doubled = reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 2).unwrap();
+    let plus_one = reactor
+        .create_compute(&[CellId::Compute(doubled)], |v| v[0] + 1)
+        .unwrap();
+    assert!(reactor.add_callback(plus_one, |v| cb.callback_called(v)).is_some());
+    reactor.remove_cell(scratch).unwrap();
+
+    let mapping = reactor.compact();
+    assert_eq!(mapping.len(), 3);
+    assert_eq!(mapping[&CellId::Input(input)], CellId::Input(input));
+    let doubled = mapping[&CellId::Compute(doubled)];
+    let plus_one = mapping[&CellId::Compute(plus_one)];
+    assert_eq!(doubled, CellId::Compute(scratch));
+    assert_eq!(reactor.dependencies(plus_one), Some(vec![doubled]));
+    assert_eq!(reactor.dependents(CellId::Input(input)), Some(vec![doubled]));
+    assert_eq!(reactor.validate(), Ok(()));
+
+    assert!(reactor.set_value(input, 5));
+    cb.expect_to_have_been_called_with(11);
+    assert_eq!(reactor.value(doubled), Some(10));
+    let fresh = reactor.create_compute(&[CellId::Input(input)], |v| v[0]).unwrap();
+    assert!(!mapping.values().any(|&id| id == CellId::Compute(fresh)));
+}
+
+#[test]
+fn compact_drops_queued_notifications_about_removed_cells() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let removed = reactor.create_compute(&[CellId::Input(input)], |v| v[0] + 1).unwrap();
+    let kept = reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 2).unwrap();
+    reactor.set_queue_notifications(true);
+    assert!(reactor.set_value(input, 5));
+    reactor.remove_cell(removed).unwrap();
+
+    let mapping = reactor.compact();
+    let kept = mapping[&CellId::Compute(kept)];
+    assert_eq!(
+        reactor.drain_notifications(),
+        vec![(CellId::Input(input), 5), (kept, 10)]
+    );
+}
+
+#[test]
+fn frozen_cells_hold_their_value_until_unfrozen() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let doubled = reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 2).unwrap();
+    let plus_one = reactor
+        .create_compute(&[CellId::Compute(doubled)], |v| v[0] + 1)
+        .unwrap();
+    assert!(reactor.add_callback(doubled, |v| cb.callback_called(v)).is_some());
+
+    assert!(reactor.freeze(doubled));
+    assert!(reactor.set_value(input, 5));
+    cb.expect_not_to_have_been_called();
+    assert_eq!(reactor.value(CellId::Compute(doubled)), Some(2));
+    assert_eq!(reactor.value(CellId::Compute(plus_one)), Some(3));
+
+    assert!(reactor.unfreeze(doubled));
+    cb.expect_to_have_been_called_with(10);
+    assert_eq!(reactor.value(CellId::Compute(plus_one)), Some(11));
+    assert!(reactor.unfreeze(doubled));
+    cb.expect_not_to_have_been_called();
+
+    reactor.remove_cell(plus_one).unwrap();
+    assert!(!reactor.freeze(plus_one));
+}
+
+#[test]
+fn create_cell_errors_describe_themselves() {
+    let mut reactor = Reactor::<i32>::new();
+    let input = CellId::Input(reactor.create_input(1));
+    let err = reactor.create_compute(&[], |_| 0).unwrap_err();
+    assert_eq!(err.to_string(), "a compute cell needs at least one dependency");
+
+    let mut other = Reactor::new();
+    other.create_input(0);
+    let missing = CellId::Input(other.create_input(0));
+    let err: Box<dyn std::error::Error> =
+        Box::new(reactor.create_compute(&[input, missing], |v| v[0]).unwrap_err());
+    assert_eq!(err.to_string(), "dependency input1 does not exist");
+}
+
+#[test]
+fn history_keeps_the_last_distinct_settled_values() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let remainder = reactor.create_compute(&[CellId::Input(input)], |v| v[0] % 3).unwrap();
+    assert_eq!(reactor.history(remainder), None);
+    assert!(reactor.enable_history(remainder, 3));
+    assert_eq!(reactor.history(remainder), Some(&[][..]));
+
+    for value in [2, 5, 3, 4, 7, 8] {
+        assert!(reactor.set_value(input, value));
+    }
+    assert_eq!(reactor.history(remainder), Some(&[0, 1, 2][..]));
+}
+
+#[test]
+fn inputs_can_be_created_in_bulk() {
+    let mut reactor = Reactor::new();
+    let zeros = reactor.create_inputs(3, 0);
+    let row = reactor.create_inputs_from(&[1, 2, 3]);
+    assert_eq!(zeros.len(), 3);
+    assert_eq!(reactor.input_count(), 6);
+    assert!(zeros.iter().all(|&id| reactor.value(CellId::Input(id)) == Some(0)));
+    let values: Vec<_> = row.iter().map(|&id| reactor.value(CellId::Input(id))).collect();
+    assert_eq!(values, vec![Some(1), Some(2), Some(3)]);
+    assert!(reactor.create_inputs_from(&[]).is_empty());
+}
+
+#[test]
+fn filtered_callbacks_only_fire_for_significant_changes() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1.0);
+    let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 2.0).unwrap();
+    let significant = |old: &f64, new: &f64| (old - new).abs() > 0.5;
+    assert!(reactor
+        .add_callback_filtered(output, significant, |v| cb.callback_called(v as i32))
+        .is_some());
+
+    assert!(reactor.set_value(input, 1.1));
+    cb.expect_not_to_have_been_called();
+    assert!(reactor.set_value(input, 3.0));
+    cb.expect_to_have_been_called_with(6);
+}
+
+#[test]
+fn modify_input_mutates_in_place_and_propagates_changes() {
+    let cb = std::sync::Mutex::new(Vec::new());
+    let mut reactor = Reactor::new();
+    let name = reactor.create_input(String::from("ada"));
+    let greeting = reactor
+        .create_compute(&[CellId::Input(name)], |v| format!("hello {}", v[0]))
+        .unwrap();
+    assert!(reactor.add_callback(greeting, |v| cb.lock().unwrap().push(v)).is_some());
+
+    assert!(reactor.modify_input(name, |s| s.push_str(" lovelace")));
+    assert!(reactor.modify_input(name, |s| s.make_ascii_lowercase()));
+    assert_eq!(*cb.lock().unwrap(), vec!["hello ada lovelace".to_string()]);
+    assert_eq!(
+        reactor.value(CellId::Compute(greeting)),
+        Some("hello ada lovelace".to_string())
+    );
+}
+
+#[test]
+fn leaf_and_dead_cells_find_unused_compute_cells() {
+    let mut reactor = Reactor::new();
+    let input = CellId::Input(reactor.create_input(1));
+    let inner = reactor.create_compute(&[input], |v| v[0] + 1).unwrap();
+    let watched = reactor.create_compute(&[CellId::Compute(inner)], |v| v[0] * 2).unwrap();
+    let unused = reactor.create_compute(&[input], |v| v[0] - 1).unwrap();
+    assert!(reactor.add_callback(watched, |_| ()).is_some());
+
+    let leaves = reactor.leaf_cells();
+    assert_eq!(leaves.len(), 2);
+    assert!(leaves.contains(&watched) && leaves.contains(&unused));
+    assert_eq!(reactor.dead_cells(), vec![unused]);
+}
+
+#[test]
+fn invalidate_recomputes_a_cell_and_its_descendants() {
+    let cb = CallbackRecorder::new();
+    let offset = SyncCell::new(0);
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let shifted = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + offset.get())
+        .unwrap();
+    let doubled = reactor
+        .create_compute(&[CellId::Compute(shifted)], |v| v[0] * 2)
+        .unwrap();
+    assert!(reactor.add_callback(doubled, |v| cb.callback_called(v)).is_some());
+
+    offset.set(10);
+    assert_eq!(reactor.value(CellId::Compute(doubled)), Some(2));
+    assert!(reactor.invalidate(shifted));
+    cb.expect_to_have_been_called_with(22);
+    assert!(reactor.invalidate(shifted));
+    cb.expect_not_to_have_been_called();
+}
+
+#[test]
+fn labels_replace_ids_in_debug_output() {
+    let mut reactor = Reactor::new();
+    let price = CellId::Input(reactor.create_input(3));
+    let total_id = reactor.create_compute(&[price], |v| v[0] * 2).unwrap();
+    let total = CellId::Compute(total_id);
+    assert!(reactor.set_label(price, "price"));
+    assert!(reactor.set_label(total, "total"));
+    assert_eq!(reactor.label(price), Some("price"));
+
+    let dump = reactor.debug_dump();
+    assert!(dump.starts_with("price input value=3 parents=[] children=[total]"));
+    let dot = reactor.to_dot();
+    assert!(dot.contains("label=\"total = 6\""));
+    assert!(dot.contains("input0 -> compute0;"));
+
+    let unlabelled = reactor.create_constant(1);
+    assert_eq!(reactor.label(unlabelled), None);
+    reactor.remove_cell(total_id).unwrap();
+    assert!(!reactor.set_label(total, "gone"));
+}
+
+#[test]
+fn diff_reports_cells_changed_since_a_snapshot() {
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_input(10);
+    let sum = reactor
+        .create_compute(&[CellId::Input(a), CellId::Input(b)], |v| v[0] + v[1])
+        .unwrap();
+    let snap = reactor.snapshot();
+    assert!(reactor.diff(&snap).is_empty());
+
+    assert!(reactor.set_value(a, 2));
+    let added = reactor.create_compute(&[CellId::Input(b)], |v| v[0]).unwrap();
+    assert_eq!(
+        reactor.diff(&snap),
+        vec![(CellId::Input(a), 1, 2), (CellId::Compute(sum), 11, 12)]
+    );
+    assert!(!reactor.diff(&snap).iter().any(|(id, _, _)| *id == CellId::Compute(added)));
+}
+
+#[test]
+// `SharedComputeFn` is an `Arc` with the feature, and this builds it by hand.
+#[cfg(not(feature = "thread-safe"))]
+fn forks_share_compute_functions_and_callbacks() {
+    let evaluations = SyncCell::new(0);
+    let seen = std::sync::Mutex::new(Vec::new());
+    let func: SharedComputeFn<i32> = std::rc::Rc::new(|v: &[i32]| {
+        evaluations.set(evaluations.get() + 1);
+        v[0] * 2
+    });
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor
+        .create_compute_shared(&[CellId::Input(input)], std::rc::Rc::clone(&func))
+        .unwrap();
+    assert_eq!(std::rc::Rc::strong_count(&func), 2);
+    assert!(reactor.add_callback(output, |v| seen.lock().unwrap().push(v)).is_some());
+
+    let mut fork = reactor.fork();
+    assert!(fork.set_value(input, 5));
+    assert!(reactor.set_value(input, 3));
+    assert_eq!(fork.value(CellId::Compute(output)), Some(10));
+    assert_eq!(reactor.value(CellId::Compute(output)), Some(6));
+    assert_eq!(evaluations.get(), 3);
+    assert_eq!(*seen.lock().unwrap(), vec![10, 6]);
+}
+
+#[test]
+fn forks_leave_one_shot_callbacks_with_the_original() {
+    let once = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] + 1).unwrap();
+    let callback = reactor.add_callback_once(output, |v| once.callback_called(v)).unwrap();
+
+    let mut fork = reactor.fork();
+    assert_eq!(fork.callbacks(output), Some(vec![]));
+    assert!(fork.set_value(input, 5));
+    once.expect_not_to_have_been_called();
+    assert_eq!(reactor.callbacks(output), Some(vec![callback]));
+    assert!(reactor.set_value(input, 2));
+    once.expect_to_have_been_called_with(3);
+}
+
+#[test]
+fn callback_limit_caps_callbacks_per_cell() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0]).unwrap();
+    reactor.set_callback_limit(2);
+    let first = reactor.add_callback(output, |_| ()).unwrap();
+    assert!(reactor.add_callback(output, |_| ()).is_some());
+    assert_eq!(reactor.add_callback(output, |_| ()), None);
+    assert_eq!(
+        reactor.try_add_callback(output, |_| ()),
+        Err(AddCallbackError::LimitReached)
+    );
+
+    assert!(reactor.remove_callback(output, first).is_ok());
+    assert!(reactor.add_callback(output, |_| ()).is_some());
+}
+
+#[test]
+fn depth_is_the_longest_path_from_an_input() {
+    let mut reactor = Reactor::new();
+    let input = CellId::Input(reactor.create_input(1));
+    let constant = reactor.create_constant(2);
+    let a = CellId::Compute(reactor.create_compute(&[input], |v| v[0]).unwrap());
+    let b = CellId::Compute(reactor.create_compute(&[a], |v| v[0]).unwrap());
+    let c = CellId::Compute(reactor.create_compute(&[input, b, constant], |v| v[0]).unwrap());
+    assert_eq!(reactor.depth(input), Some(0));
+    assert_eq!(reactor.depth(constant), Some(0));
+    assert_eq!(reactor.depth(a), Some(1));
+    assert_eq!(reactor.depth(c), Some(3));
+
+    let other = Reactor::<i32>::new().create_constant(0);
+    assert_eq!(Reactor::<i32>::new().depth(other), None);
+}
+
+#[test]
+fn select_follows_the_chosen_branch() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let cond = reactor.create_input(true);
+    let yes = reactor.create_input(true);
+    let no = reactor.create_input(false);
+    let select = reactor
+        .create_select(CellId::Input(cond), CellId::Input(yes), CellId::Input(no))
+        .unwrap();
+    assert_eq!(reactor.value(CellId::Compute(select)), Some(true));
+    assert!(reactor
+        .add_callback(select, |v| cb.callback_called(v as i32))
+        .is_some());
+
+    assert!(reactor.set_value(no, true));
+    cb.expect_not_to_have_been_called();
+    assert!(reactor.set_value(yes, false));
+    cb.expect_to_have_been_called_with(0);
+    assert!(reactor.set_value(cond, false));
+    cb.expect_to_have_been_called_with(1);
+}
+
+#[test]
+fn all_values_maps_every_cell_to_its_value() {
+    let mut reactor = Reactor::new();
+    let input = CellId::Input(reactor.create_input(1));
+    let constant = reactor.create_constant(10);
+    let sum = CellId::Compute(reactor.create_compute(&[input, constant], |v| v[0] + v[1]).unwrap());
+    let expected = HashMap::from([(input, 1), (constant, 10), (sum, 11)]);
+    assert_eq!(reactor.all_values(), expected);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn writes_after_an_interrupted_propagation_are_rejected_until_recompute_all() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0]).unwrap();
+    assert!(reactor
+        .add_callback(output, |v| assert_ne!(v, 2, "callback failed"))
+        .is_some());
+
+    let interrupted = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        reactor.set_value(input, 2)
+    }));
+    assert!(interrupted.is_err());
+    assert_eq!(reactor.try_set_value(input, 3), Err(PropagationError::Reentrant));
+    assert_eq!(reactor.value(CellId::Input(input)), Some(2));
+
+    let mut fork = reactor.fork();
+    assert_eq!(fork.try_set_value(input, 3), Err(PropagationError::Reentrant));
+    assert_eq!(reactor.recompute_all(), Ok(()));
+    assert_eq!(reactor.try_set_value(input, 3), Ok(true));
+    assert_eq!(reactor.value(CellId::Compute(output)), Some(3));
+    assert_eq!(fork.recompute_all(), Ok(()));
+    assert_eq!(fork.try_set_value(input, 4), Ok(true));
+}
+
+#[test]
+fn input_validators_clamp_or_reject_writes() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let volume = reactor.create_input(50);
+    let output = reactor.create_compute(&[CellId::Input(volume)], |v| v[0]).unwrap();
+    assert!(reactor.add_callback(output, |v| cb.callback_called(v)).is_some());
+    assert!(reactor.set_input_validator(volume, |v: i32| (v >= 0).then(|| v.min(100))));
+
+    assert!(reactor.set_value(volume, 150));
+    cb.expect_to_have_been_called_with(100);
+    assert!(!reactor.set_value(volume, -1));
+    assert_eq!(reactor.try_set_value(volume, -1), Err(PropagationError::Rejected));
+    assert!(!reactor.modify_input(volume, |v| *v = -5));
+    assert!(!reactor.set_value_batch(&[(volume, 10), (volume, -1)]));
+    cb.expect_not_to_have_been_called();
+    assert_eq!(reactor.value(CellId::Input(volume)), Some(100));
+}
+
+#[test]
+fn recompute_count_includes_runs_that_changed_nothing() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let parity = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] % 2)
+        .unwrap();
+    assert_eq!(reactor.recompute_count(parity), Some(0));
+    assert!(reactor.set_value(input, 3));
+    assert!(reactor.set_value(input, 4));
+    assert_eq!(reactor.recompute_count(parity), Some(2));
+    assert_eq!(reactor.value(CellId::Compute(parity)), Some(0));
+}
+
+#[test]
+fn clear_callbacks_removes_every_callback_on_a_cell() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0]).unwrap();
+    let first = reactor.add_callback(output, |v| cb.callback_called(v)).unwrap();
+    assert!(reactor.add_callback(output, |v| cb.callback_called(v)).is_some());
+
+    assert_eq!(reactor.clear_callbacks(output), Some(2));
+    assert!(reactor.set_value(input, 2));
+    cb.expect_not_to_have_been_called();
+    assert_eq!(
+        reactor.remove_callback(output, first),
+        Err(RemoveCallbackError::NonexistentCallback)
+    );
+    assert_eq!(reactor.clear_callbacks(output), Some(0));
+}
+
+#[test]
+fn fixed_arity_compute_cells_take_values_in_order() {
+    let mut reactor = Reactor::new();
+    let a = CellId::Input(reactor.create_input(10));
+    let b = reactor.create_input(3);
+    let c = reactor.create_constant(2);
+    let two = reactor.create_compute2(a, CellId::Input(b), |x, y| x - y).unwrap();
+    let three = reactor
+        .create_compute3(a, CellId::Input(b), c, |x, y, z| (x - y) * z)
+        .unwrap();
+    assert_eq!(reactor.value(CellId::Compute(two)), Some(7));
+    assert_eq!(reactor.value(CellId::Compute(three)), Some(14));
+    assert!(reactor.set_value(b, 4));
+    assert_eq!(reactor.value(CellId::Compute(two)), Some(6));
+    assert_eq!(reactor.value(CellId::Compute(three)), Some(12));
+}
+
+#[test]
+fn would_change_previews_a_write_without_applying_it() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let parity = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] % 2)
+        .unwrap();
+    let doubled = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] * 2)
+        .unwrap();
+    let flag = reactor
+        .create_compute(&[CellId::Compute(parity)], |v| v[0] * 10)
+        .unwrap();
+    assert!(reactor.add_callback(doubled, |v| cb.callback_called(v)).is_some());
+
+    assert_eq!(reactor.would_change(input, 3), vec![doubled]);
+    let mut changed = reactor.would_change(input, 2);
+    changed.sort_by_key(|id| reactor.depth(CellId::Compute(*id)));
+    assert_eq!(changed.len(), 3);
+    assert_eq!(changed[2], flag);
+    assert!(reactor.would_change(input, 1).is_empty());
+
+    cb.expect_not_to_have_been_called();
+    assert_eq!(reactor.value(CellId::Input(input)), Some(1));
+    assert_eq!(reactor.value(CellId::Compute(doubled)), Some(2));
+}
+
+#[test]
+fn reactors_can_be_collected_and_extended_with_inputs() {
+    let mut reactor: Reactor<i32> = (1..=3).collect();
+    reactor.extend([4, 5]);
+    assert_eq!(reactor.input_count(), 5);
+    let mut values: Vec<i32> = reactor.iter_cells().map(|(_, v)| v).collect();
+    values.sort();
+    assert_eq!(values, vec![1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn adjacency_lists_children_and_parents_of_every_cell() {
+    let mut reactor = Reactor::new();
+    let input = CellId::Input(reactor.create_input(1));
+    let constant = reactor.create_constant(2);
+    let sum = CellId::Compute(reactor.create_compute(&[input, constant], |v| v[0] + v[1]).unwrap());
+    let double = CellId::Compute(reactor.create_compute(&[sum], |v| v[0] * 2).unwrap());
+    assert_eq!(
+        reactor.adjacency(),
+        vec![
+            (input, vec![sum]),
+            (constant, vec![sum]),
+            (sum, vec![double]),
+            (double, vec![]),
+        ]
+    );
+    assert_eq!(
+        reactor.reverse_adjacency(),
+        vec![
+            (input, vec![]),
+            (constant, vec![]),
+            (sum, vec![input, constant]),
+            (double, vec![sum]),
+        ]
+    );
+}
+
+#[test]
+fn observer_objects_receive_changes_with_the_cell_id() {
+    struct Recorder<'r>(&'r std::sync::Mutex<Vec<(ComputeCellId, i32)>>);
+
+    impl CellObserver<i32> for Recorder<'_> {
+        fn on_change(&mut self, id: ComputeCellId, value: i32) {
+            self.0.lock().unwrap().push((id, value));
+        }
+    }
+
+    let seen = std::sync::Mutex::new(Vec::new());
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] + 1).unwrap();
+    let observer = reactor.add_observer(output, Box::new(Recorder(&seen))).unwrap();
+    assert!(reactor.set_value(input, 2));
+    assert!(reactor.remove_callback(output, observer).is_ok());
+    assert!(reactor.set_value(input, 3));
+    assert_eq!(*seen.lock().unwrap(), vec![(output, 3)]);
+}
+
+#[test]
+fn observers_are_given_the_id_a_cell_has_after_compact() {
+    struct Recorder<'r>(&'r std::sync::Mutex<Vec<(ComputeCellId, i32)>>);
+
+    impl CellObserver<i32> for Recorder<'_> {
+        fn on_change(&mut self, id: ComputeCellId, value: i32) {
+            self.0.lock().unwrap().push((id, value));
+        }
+    }
+
+    let seen = std::sync::Mutex::new(Vec::new());
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let scratch = reactor.create_compute(&[CellId::Input(input)], |v| v[0]).unwrap();
+    let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] + 1).unwrap();
+    assert!(reactor.add_observer(output, Box::new(Recorder(&seen))).is_some());
+    reactor.remove_cell(scratch).unwrap();
+
+    let CellId::Compute(output) = reactor.compact()[&CellId::Compute(output)] else {
+        panic!("compute cells stay compute cells");
+    };
+    assert!(reactor.set_value(input, 2));
+    assert_eq!(*seen.lock().unwrap(), vec![(output, 3)]);
+    assert_eq!(output, scratch);
+}
+
+#[test]
+fn expect_accessors_return_values_of_existing_cells() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    assert!(reactor.set_value_expect(input, 2));
+    assert_eq!(reactor.value_expect(CellId::Input(input)), 2);
+}
+
+#[test]
+#[should_panic(expected = "cell input0 does not exist")]
+fn value_expect_panics_on_unknown_ids() {
+    let mut other = Reactor::new();
+    let input = other.create_input(0);
+    Reactor::<i32>::new().value_expect(CellId::Input(input));
+}
+
+#[test]
+#[should_panic(expected = "cell input0 does not exist")]
+fn set_value_expect_panics_on_unknown_ids() {
+    let mut other = Reactor::new();
+    let input = other.create_input(0);
+    Reactor::new().set_value_expect(input, 1);
+}
+
+#[test]
+fn children_of_unchanged_cells_are_not_recomputed() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let coarse = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] / 10)
+        .unwrap();
+    let fine = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] + 1)
+        .unwrap();
+    let below = reactor
+        .create_compute(&[CellId::Compute(coarse)], |v| v[0] * 2)
+        .unwrap();
+
+    assert!(reactor.set_value(input, 2));
+    assert!(reactor.invalidate(coarse));
+    assert_eq!(reactor.recompute_count(coarse), Some(2));
+    assert_eq!(reactor.recompute_count(fine), Some(1));
+    assert_eq!(reactor.recompute_count(below), Some(0));
+}
+
+#[test]
+fn source_callbacks_receive_the_input_that_was_written() {
+    let seen = std::sync::Mutex::new(Vec::new());
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_input(2);
+    let sum = reactor
+        .create_compute(&[CellId::Input(a), CellId::Input(b)], |v| v[0] + v[1])
+        .unwrap();
+    assert!(reactor
+        .add_callback_with_source(sum, |v, source| seen.lock().unwrap().push((v, source)))
+        .is_some());
+
+    assert!(reactor.set_value(b, 3));
+    assert!(reactor.set_value_batch(&[(a, 1), (b, 4), (a, 2)]));
+    assert_eq!(*seen.lock().unwrap(), vec![(4, b), (6, b)]);
+}
+
+#[test]
+fn change_log_records_writes_and_what_they_changed() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let parity = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] % 2)
+        .unwrap();
+    assert!(reactor.set_value(input, 2));
+    assert!(reactor.change_log().is_empty());
+
+    reactor.enable_change_log();
+    assert!(reactor.set_value(input, 4));
+    assert!(reactor.set_value(input, 5));
+    let log = reactor.change_log();
+    assert_eq!(log.len(), 2);
+    assert_eq!((log[0].input(), *log[0].old_value(), *log[0].new_value()), (input, 2, 4));
+    assert!(log[0].affected().is_empty());
+    assert_eq!(log[1].affected(), &[(parity, 1)]);
+    assert_eq!(log[1].generation(), reactor.generation());
+
+    reactor.clear_change_log();
+    assert!(reactor.change_log().is_empty());
+    assert!(reactor.set_value(input, 6));
+    assert_eq!(reactor.change_log().len(), 1);
+}
+
+#[test]
+fn switching_compute_picks_the_closure_for_the_mode() {
+    let mut reactor = Reactor::new();
+    let mode = reactor.create_input(0);
+    let a = reactor.create_input(6);
+    let b = reactor.create_input(3);
+    let funcs: Vec<(i32, BoxedComputeFn<i32>)> = vec![
+        (0, Box::new(|v: &[i32]| v[0] + v[1])),
+        (1, Box::new(|v: &[i32]| v[0] * v[1])),
+    ];
+    let output = reactor
+        .create_switching_compute(CellId::Input(mode), funcs, &[CellId::Input(a), CellId::Input(b)])
+        .unwrap();
+    assert_eq!(reactor.value(CellId::Compute(output)), Some(9));
+    assert!(reactor.set_value(mode, 1));
+    assert_eq!(reactor.value(CellId::Compute(output)), Some(18));
+    assert!(reactor.set_value(b, 2));
+    assert_eq!(reactor.value(CellId::Compute(output)), Some(12));
+    assert!(reactor.set_value(mode, 7));
+    assert_eq!(reactor.value(CellId::Compute(output)), Some(8));
+
+    let err = reactor
+        .create_switching_compute(CellId::Input(mode), Vec::new(), &[CellId::Input(a)])
+        .unwrap_err();
+    assert_eq!(err, CreateCellError::NoFunctions);
+    assert_eq!(err.to_string(), "a switching cell needs at least one function");
+}
+
+#[test]
+fn checked_sums_fail_on_overflow_and_recover() {
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(i32::MAX - 1);
+    let b = reactor.create_input(1);
+    let sum = reactor
+        .create_checked_sum(&[CellId::Input(a), CellId::Input(b)])
+        .unwrap();
+    assert_eq!(reactor.value(CellId::Compute(sum)), Some(i32::MAX));
+
+    assert!(reactor.set_value(b, 2));
+    assert_eq!(reactor.value(CellId::Compute(sum)), None);
+    assert_eq!(reactor.error::<SumOverflow>(sum), Some(&SumOverflow));
+
+    assert!(reactor.set_value(a, i32::MIN));
+    assert_eq!(reactor.value(CellId::Compute(sum)), Some(i32::MIN + 2));
+    let c = reactor.create_input(-3);
+    assert_eq!(
+        reactor.create_checked_sum(&[CellId::Input(a), CellId::Input(c)]),
+        Err(CreateCellError::ComputeFailed)
+    );
+}
+
+#[test]
+fn ids_expose_their_index() {
+    let mut reactor = Reactor::new();
+    let first = reactor.create_input(1);
+    let second = reactor.create_input(2);
+    let output = reactor.create_compute(&[CellId::Input(second)], |v| v[0]).unwrap();
+    let callback = reactor.add_callback(output, |_| ()).unwrap();
+    let mut values = vec![0; 2];
+    values[second.index()] = 2;
+    assert_eq!((first.index(), second.index()), (0, 1));
+    assert_eq!(values, vec![0, 2]);
+    assert_eq!(output.index(), 0);
+    assert_ne!(callback.index(), reactor.add_callback(output, |_| ()).unwrap().index());
+}
+
+#[test]
+fn repointing_a_dependency_keeps_the_cell_and_its_callbacks() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_input(10);
+    let output = reactor
+        .create_compute(&[CellId::Input(a)], |v| v[0] + 1)
+        .unwrap();
+    let below = reactor
+        .create_compute(&[CellId::Compute(output)], |v| v[0])
+        .unwrap();
+    assert!(reactor.add_callback(output, |v| cb.callback_called(v)).is_some());
+
+    assert_eq!(reactor.repoint_dependency(output, CellId::Input(a), CellId::Input(b)), Ok(()));
+    cb.expect_to_have_been_called_with(11);
+    assert_eq!(reactor.dependencies(CellId::Compute(output)), Some(vec![CellId::Input(b)]));
+    assert_eq!(reactor.dependents(CellId::Input(a)), Some(vec![]));
+    assert!(reactor.set_value(a, 5));
+    cb.expect_not_to_have_been_called();
+
+    assert_eq!(
+        reactor.repoint_dependency(output, CellId::Input(a), CellId::Input(b)),
+        Err(CreateCellError::NonexistentDependency(CellId::Input(a)))
+    );
+    assert!(matches!(
+        reactor.repoint_dependency(output, CellId::Input(b), CellId::Compute(below)),
+        Err(CreateCellError::Cycle(_))
+    ));
+    assert_eq!(reactor.validate(), Ok(()));
+}
+
+#[test]
+fn duplicate_dependencies_are_rejected() {
+    let mut reactor = Reactor::new();
+    let a = CellId::Input(reactor.create_input(1));
+    let b = CellId::Input(reactor.create_input(2));
+    assert_eq!(
+        reactor.create_compute(&[a, b, a], |v| v[0]),
+        Err(CreateCellError::DuplicateDependency(a))
+    );
+    assert_eq!(reactor.dependents(a), Some(vec![]));
+
+    let output = reactor.create_compute(&[a], |v| v[0]).unwrap();
+    assert_eq!(
+        reactor.add_dependency(output, a),
+        Err(CreateCellError::DuplicateDependency(a))
+    );
+    assert!(reactor.add_dependency(output, b).is_ok());
+    assert_eq!(
+        reactor.repoint_dependency(output, b, a),
+        Err(CreateCellError::DuplicateDependency(a))
+    );
+    assert_eq!(reactor.dependents(a), Some(vec![CellId::Compute(output)]));
+}
+
+#[test]
+fn fallback_cells_stand_in_for_errored_dependencies() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(4);
+    let root = reactor
+        .create_try_compute(&[CellId::Input(input)], |v| {
+            if v[0] < 0 {
+                Err("negative")
+            } else {
+                Ok(v[0])
+            }
+        })
+        .unwrap();
+    let plain = reactor.create_compute(&[CellId::Compute(root)], |v| v[0] + 1).unwrap();
+    let deps = [CellId::Compute(root), CellId::Input(input)];
+    let safe = reactor
+        .create_compute_with_fallback(&deps, 0, |v| v[0] + v[1])
+        .unwrap();
+    assert_eq!(reactor.value(CellId::Compute(safe)), Some(8));
+
+    assert!(reactor.set_value(input, -1));
+    assert_eq!(reactor.value(CellId::Compute(plain)), None);
+    assert_eq!(reactor.value(CellId::Compute(safe)), Some(-1));
+    assert!(reactor.would_change(input, -2).contains(&safe));
+
+    let late = reactor
+        .create_compute_with_fallback(&[CellId::Compute(root)], 10, |v| v[0])
+        .unwrap();
+    assert_eq!(reactor.value(CellId::Compute(late)), Some(10));
+    assert!(reactor.set_value(input, 3));
+    assert_eq!(reactor.value(CellId::Compute(late)), Some(3));
+}
+
+#[test]
+fn visitors_see_parents_before_children() {
+    let mut reactor = Reactor::new();
+    let input = CellId::Input(reactor.create_input(1));
+    let a = CellId::Compute(reactor.create_compute(&[input], |v| v[0]).unwrap());
+    let b = CellId::Compute(reactor.create_compute(&[input, a], |v| v[0]).unwrap());
+
+    let mut costs: HashMap<CellId, usize> = Default::default();
+    reactor.visit_topological(|id, parents| {
+        let cost = 1 + parents.iter().map(|p| costs[p]).sum::<usize>();
+        costs.insert(id, cost);
+    });
+    assert_eq!((costs[&input], costs[&a], costs[&b]), (1, 2, 4));
+}
+
+#[test]
+fn compute_functions_can_be_replaced_by_label() {
+    let mut reactor = Reactor::new();
+    let a = CellId::Input(reactor.create_input(2));
+    let b = CellId::Input(reactor.create_input(3));
+    let total = reactor.create_compute(&[a, b], |v| v[0] + v[1]).unwrap();
+    let other = reactor.create_compute(&[a], |v| v[0]).unwrap();
+    assert!(reactor.set_label(CellId::Compute(total), "total"));
+
+    assert!(reactor.replace_compute_func_by_label("total", |v| v[0] * v[1]));
+    assert_eq!(reactor.value(CellId::Compute(total)), Some(6));
+    assert!(!reactor.replace_compute_func_by_label("missing", |v| v[0]));
+
+    assert!(reactor.set_label(CellId::Compute(other), "total"));
+    assert!(!reactor.replace_compute_func_by_label("total", |v| v[0]));
+    assert_eq!(reactor.value(CellId::Compute(total)), Some(6));
+}
+
+#[test]
+fn frames_coalesce_writes_into_one_propagation() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let x = reactor.create_input(0);
+    let y = reactor.create_input(0);
+    let output = reactor
+        .create_compute(&[CellId::Input(x), CellId::Input(y)], |v| v[0] + v[1])
+        .unwrap();
+    assert!(reactor.add_callback(output, |v| cb.callback_called(v)).is_some());
+
+    reactor.begin_frame();
+    for i in 1..=10 {
+        assert!(reactor.set_value(x, i));
+    }
+    assert!(reactor.set_value_batch(&[(y, 5), (y, 0)]));
+    assert_eq!(reactor.value(CellId::Input(x)), Some(0));
+    cb.expect_not_to_have_been_called();
+    assert_eq!(reactor.end_frame(), Ok(()));
+
+    cb.expect_to_have_been_called_with(10);
+    assert_eq!(reactor.recompute_count(output), Some(1));
+    assert_eq!(reactor.value(CellId::Input(y)), Some(0));
+}
+
+#[test]
+fn frames_hold_back_modify_input_and_reset_inputs() {
+    let cb = CallbackRecorder::new();
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 10).unwrap();
+    assert!(reactor.add_callback(output, |v| cb.callback_called(v)).is_some());
+
+    reactor.begin_frame();
+    assert!(reactor.set_value(input, 5));
+    assert!(reactor.modify_input(input, |v| *v += 2));
+    cb.expect_not_to_have_been_called();
+    assert_eq!(reactor.end_frame(), Ok(()));
+    cb.expect_to_have_been_called_with(70);
+    assert_eq!(reactor.value(CellId::Input(input)), Some(7));
+
+    reactor.begin_frame();
+    assert_eq!(reactor.reset_inputs(), Ok(()));
+    assert_eq!(reactor.value(CellId::Input(input)), Some(7));
+    assert_eq!(reactor.end_frame(), Ok(()));
+    cb.expect_to_have_been_called_with(10);
+}
+
+#[test]
+fn batch_plans_list_affected_cells_parents_first() {
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_input(2);
+    let c = reactor.create_input(3);
+    let ab = reactor
+        .create_compute(&[CellId::Input(a), CellId::Input(b)], |v| v[0] + v[1])
+        .unwrap();
+    let top = reactor
+        .create_compute(&[CellId::Compute(ab)], |v| v[0] * 2)
+        .unwrap();
+    let frozen = reactor.create_compute(&[CellId::Input(c)], |v| v[0]).unwrap();
+    reactor.create_compute(&[CellId::Compute(frozen)], |v| v[0]).unwrap();
+    assert!(reactor.freeze(frozen));
+
+    assert_eq!(reactor.plan_batch(&[(b, 5)]), vec![ab, top]);
+    assert!(reactor.plan_batch(&[(c, 5)]).is_empty());
+    assert_eq!(reactor.plan_batch(&[(c, 4), (a, 0)]), vec![ab, top]);
+    assert!(reactor.plan_batch(&[(a, 1), (b, 2)]).is_empty());
+    assert_eq!(reactor.value(CellId::Input(b)), Some(2));
+}
+
+#[test]
+fn writes_that_change_nothing_recompute_nothing() {
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_input(2);
+    let sum = reactor
+        .create_compute(&[CellId::Input(a), CellId::Input(b)], |v| v[0] + v[1])
+        .unwrap();
+    assert_eq!(reactor.reset_inputs(), Ok(()));
+    assert!(reactor.set_value_batch(&[(a, 1)]));
+    assert_eq!(reactor.recompute_count(sum), Some(0));
+
+    reactor.begin_transaction();
+    assert!(reactor.set_value(a, 5));
+    assert!(reactor.set_value(a, 1));
+    assert_eq!(reactor.commit(), Ok(()));
+    assert_eq!(reactor.recompute_count(sum), Some(0));
+    assert!(reactor.set_value_batch(&[(a, 1), (b, 3)]));
+    assert_eq!(reactor.recompute_count(sum), Some(1));
+    assert_eq!(reactor.value(CellId::Compute(sum)), Some(4));
+}
+
+#[test]
+fn compute_cells_can_produce_another_type() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let doubled = reactor
+        .create_compute(&[CellId::Input(input)], |v| v[0] * 2)
+        .unwrap();
+    let exceeded = reactor
+        .create_compute_into(&[CellId::Compute(doubled)], |v| v[0] > 10)
+        .unwrap();
+    let label = reactor
+        .create_compute_into(&[CellId::Input(input)], |v| format!("n = {}", v[0]))
+        .unwrap();
+    assert_eq!(reactor.value_typed(exceeded), Some(false));
+    assert!(reactor.set_value(input, 6));
+    assert_eq!(reactor.value_typed(exceeded), Some(true));
+    assert_eq!(reactor.value_typed(label), Some("n = 6".to_string()));
+    assert_eq!(
+        reactor.remove_cell(doubled),
+        Err(RemoveCellError::HasDependents)
+    );
+    assert!(reactor.leaf_cells().is_empty());
+    assert!(reactor.dead_cells().is_empty());
+}
+
+#[test]
+fn typed_compute_cells_follow_a_restore() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let exceeded = reactor
+        .create_compute_into(&[CellId::Input(input)], |v| v[0] > 10)
+        .unwrap();
+    let snap = reactor.snapshot();
+    assert!(reactor.set_value(input, 20));
+    assert_eq!(reactor.value_typed(exceeded), Some(true));
+    assert_eq!(reactor.restore(&snap), Ok(()));
+    assert_eq!(reactor.value_typed(exceeded), Some(false));
+}
+
+#[test]
+fn diamonds_recompute_each_cell_once() {
+    let mut reactor = Reactor::new();
+    let a = reactor.create_input(1);
+    let b = reactor.create_compute(&[CellId::Input(a)], |v| v[0] + 1).unwrap();
+    let c = reactor.create_compute(&[CellId::Input(a)], |v| v[0] * 2).unwrap();
+    let d = reactor
+        .create_compute(&[CellId::Compute(b), CellId::Compute(c)], |v| v[0] + v[1])
+        .unwrap();
+    assert!(reactor.set_value(a, 2));
+    reactor.assert_single_recompute();
+    assert_eq!(reactor.recompute_count(b), Some(1));
+    assert_eq!(reactor.recompute_count(c), Some(1));
+    assert_eq!(reactor.recompute_count(d), Some(1));
+    assert_eq!(reactor.value(CellId::Compute(d)), Some(7));
+}
+
+#[test]
+fn aliased_inputs_share_one_cell() {
+    let calls = SyncCell::new(0);
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let alias = reactor.alias_input(input).unwrap();
+    let sum = reactor
+        .create_compute(&[CellId::Input(input), CellId::Input(alias)], |v| v[0] + v[1])
+        .unwrap_err();
+    assert_eq!(sum, CreateCellError::DuplicateDependency(CellId::Input(input)));
+    let doubled = reactor
+        .create_compute(&[CellId::Input(alias)], |v| v[0] * 2)
+        .unwrap();
+    reactor.add_callback(doubled, |_| calls.set(calls.get() + 1));
+    assert!(reactor.set_value(alias, 5));
+    assert_eq!(reactor.value(CellId::Input(input)), Some(5));
+    assert!(reactor.set_value(input, 6));
+    assert_eq!(reactor.value(CellId::Input(alias)), Some(6));
+    assert_eq!(reactor.value(CellId::Compute(doubled)), Some(12));
+    assert_eq!(calls.get(), 2);
+    assert_eq!(reactor.iter_cells().count(), 2);
+}
+
+#[test]
+fn aliases_work_wherever_an_input_id_does() {
+    let sources = std::sync::Mutex::new(Vec::new());
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let alias = CellId::Input(reactor.alias_input(input).unwrap());
+    let doubled = reactor.create_compute(&[alias], |v| v[0] * 2).unwrap();
+    assert_eq!(reactor.dependencies(alias), Some(vec![]));
+    assert_eq!(reactor.dependents(alias), Some(vec![CellId::Compute(doubled)]));
+    assert_eq!(reactor.cell_info(alias).unwrap().children(), [CellId::Compute(doubled)]);
+    assert!(reactor.set_label(alias, "volume"));
+    assert_eq!(reactor.label(CellId::Input(input)), Some("volume"));
+
+    assert!(reactor
+        .add_callback_with_source(doubled, |_, source| sources.lock().unwrap().push(source))
+        .is_some());
+    let CellId::Input(alias_id) = alias else { unreachable!() };
+    assert!(reactor.set_value(alias_id, 2));
+    assert!(reactor.set_value(input, 3));
+    drop(reactor);
+    assert_eq!(sources.into_inner().unwrap(), vec![alias_id, input]);
+}
+
+#[test]
+fn blueprints_keep_aliases() {
+    let mut reactor: Reactor<i32> = Reactor::new();
+    let input = reactor.create_input(1);
+    let alias = reactor.alias_input(input).unwrap();
+    let mut restored =
+        Reactor::rehydrate(&reactor.blueprint(), HashMap::new()).unwrap();
+    assert!(restored.set_value(alias, 4));
+    assert_eq!(restored.value(CellId::Input(input)), Some(4));
+    let fresh = restored.create_input(0);
+    assert_ne!(fresh, alias);
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "thread-safe")]
+
+use react::*;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+fn assert_send<T: Send>() {}
+
+fn assert_sync<T: Sync>() {}
+
+#[test]
+fn reactor_is_send() {
+    assert_send::<Reactor<'static, i32>>();
+    assert_send::<Reactor<'static, String>>();
+}
+
+#[test]
+fn reactor_is_sync() {
+    assert_sync::<Reactor<'static, i32>>();
+    assert_sync::<Reactor<'static, String>>();
+}
+
+#[test]
+fn reactors_can_be_read_from_several_threads() {
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 2).unwrap();
+    let positive = reactor.create_compute_into(&[CellId::Input(input)], |v| v[0] > 0).unwrap();
+    reactor.subscribe_all(|_, _| ());
+
+    let reactor = &reactor;
+    thread::scope(|scope| {
+        for _ in 0..3 {
+            scope.spawn(move || {
+                assert_eq!(reactor.value(CellId::Compute(output)), Some(2));
+                assert_eq!(reactor.value_typed(positive), Some(true));
+            });
+        }
+    });
+}
+
+#[test]
+fn reactor_can_be_shared_behind_a_mutex() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let mut reactor = Reactor::new();
+    let input = reactor.create_input(1);
+    let output = reactor.create_compute(&[CellId::Input(input)], |v| v[0] * 2).unwrap();
+    let log = Arc::clone(&seen);
+    assert!(reactor.add_callback(output, move |v| log.lock().unwrap().push(v)).is_some());
+
+    let reactor = Arc::new(Mutex::new(reactor));
+    let handles: Vec<_> = (2..5)
+        .map(|value| {
+            let reactor = Arc::clone(&reactor);
+            thread::spawn(move || assert!(reactor.lock().unwrap().set_value(input, value)))
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let mut seen = seen.lock().unwrap().clone();
+    seen.sort();
+    assert_eq!(seen, vec![4, 6, 8]);
+}